@@ -0,0 +1,120 @@
+use chrono::{DateTime, Duration, Local};
+use console::style;
+use console::Style;
+use serde::{Deserialize, Serialize};
+
+use crate::search;
+use crate::search::SearchMode;
+use crate::storage::TodoData;
+use crate::tasks;
+use crate::tasks::Task;
+
+//How old archived tasks have to be before purge removes them, unless another age is given
+pub const DEFAULT_PURGE_DAYS: u32 = 90;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ArchivedTask {
+    pub task: Task,
+    //The list the task was cleaned from
+    pub list: String,
+    pub archived_at: DateTime<Local>,
+}
+
+impl ArchivedTask {
+    fn completed_at(&self) -> DateTime<Local> {
+        self.task.completed_at.unwrap_or(self.archived_at)
+    }
+}
+
+//Moves every completed task in the current list to the archive and returns how many were moved
+pub fn clean(data: &mut TodoData) -> usize {
+    let before = data.tasks.clone();
+    let archive_before = data.archive.clone();
+
+    let removed = tasks::remove_completed_tasks(&mut data.tasks);
+    let count = removed.len();
+    let archived_at = Local::now();
+    for task in removed {
+        data.archive.push(ArchivedTask {
+            task,
+            list: data.current_list.clone(),
+            archived_at,
+        });
+    }
+
+    if count > 0 {
+        data.history.record_archive("clean", &data.current_list, before, archive_before);
+    }
+    count
+}
+
+pub fn show_archive(archive: &[ArchivedTask]) {
+    if archive.is_empty() {
+        println!("{}", style("The archive is empty").blue());
+        return;
+    }
+
+    for entry in archive {
+        print_archived(entry, style(&entry.task.name).green().to_string());
+    }
+}
+
+pub fn search_archive(archive: &[ArchivedTask], query: &str, mode: SearchMode) -> Result<(), String> {
+    let archived: Vec<Task> = archive.iter().map(|entry| entry.task.clone()).collect();
+    let matches = search::search(&archived, query, mode)?;
+    if matches.is_empty() {
+        println!("{}", style(format!("No archived tasks match \"{}\"", query)).blue());
+    }
+
+    for found in matches {
+        let entry = &archive[found.index];
+        print_archived(entry, search::highlight(&entry.task.name, &found.highlight, &Style::new().green()));
+    }
+    Ok(())
+}
+
+fn print_archived(entry: &ArchivedTask, name: String) {
+    let details = format!("(completed {}, from {})", entry.completed_at().format("%Y-%m-%d"), entry.list);
+    let tags = if entry.task.tags.is_empty() { String::new() } else { format!(" {}", style(entry.task.tags.join(" ")).cyan().dim()) };
+    println!("{:>3}. {}{} {}", entry.task.id, name, tags, style(details).dim());
+}
+
+//Puts an archived task back in the current list. It goes back under its parent if the parent is in the list
+pub fn restore(data: &mut TodoData, id: u32) -> Result<(), String> {
+    let position = data
+        .archive
+        .iter()
+        .position(|entry| entry.task.id == id)
+        .ok_or(format!("There is no archived task with id {}", id))?;
+    data.history.record_archive("restore", &data.current_list, data.tasks.clone(), data.archive.clone());
+
+    let mut task = data.archive.remove(position).task;
+    if task.parent.is_some_and(|parent| tasks::find_task(&data.tasks, parent).is_none()) {
+        task.parent = None;
+    }
+    data.tasks.push(task);
+    Ok(())
+}
+
+//Removes archived tasks completed more than the given number of days ago and returns how many went
+pub fn purge(data: &mut TodoData, days: u32) -> usize {
+    let cutoff = cutoff(days);
+    let archive_before = data.archive.clone();
+    data.archive.retain(|entry| cutoff.is_none_or(|cutoff| entry.completed_at() >= cutoff));
+
+    let count = archive_before.len() - data.archive.len();
+    if count > 0 {
+        data.history.record_archive("purge", &data.current_list, data.tasks.clone(), archive_before);
+    }
+    count
+}
+
+pub fn count_older_than(archive: &[ArchivedTask], days: u32) -> usize {
+    let cutoff = cutoff(days);
+    archive.iter().filter(|entry| cutoff.is_some_and(|cutoff| entry.completed_at() < cutoff)).count()
+}
+
+//None when that many days ago is before the earliest date there is, so nothing can be that old
+fn cutoff(days: u32) -> Option<DateTime<Local>> {
+    Local::now().checked_sub_signed(Duration::days(days as i64))
+}
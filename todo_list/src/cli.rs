@@ -0,0 +1,600 @@
+use clap::{Parser, Subcommand};
+use dialoguer::{Confirm, Editor};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::archive;
+use crate::due;
+use crate::due::Due;
+use crate::formats;
+use crate::formats::Format;
+use crate::history;
+use crate::lists;
+use crate::search;
+use crate::search::SearchMode;
+use crate::stats;
+use crate::stats::Period;
+use crate::storage::TodoData;
+use crate::tags;
+use crate::tasks;
+use crate::tasks::{Priority, SortOrder, Task};
+use crate::timer;
+use crate::timer::Timesheet;
+
+#[derive(Parser)]
+#[command(version, about = "A simple CLI tool for managing a To-Do list")]
+#[command(after_help = "Run without a command to use the interactive menu.")]
+pub struct Cli {
+    /// Work on this list instead of the current one. Commands switch back afterwards, the menu and full-screen view stay on it
+    #[arg(long, global = true)]
+    pub list: Option<String>,
+    /// Use a tasks file shared with other people, e.g. in a synced folder, instead of your own. Changes
+    /// others save while you have it open are merged in rather than overwritten
+    #[arg(long, global = true, value_name = "PATH")]
+    pub file: Option<PathBuf>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Show all tasks
+    #[command(alias = "show")]
+    List {
+        /// Order to list tasks in, remembered for later runs
+        #[arg(long, value_enum)]
+        sort: Option<SortOrder>,
+        /// Only show tasks with this tag, e.g. +work or @phone. Can be repeated
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+    },
+    /// Show incomplete tasks due within the next few days, including overdue ones
+    Due {
+        #[arg(default_value_t = 7)]
+        days: u32,
+        /// Only show tasks with this tag. Can be repeated
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+    },
+    /// Find tasks, including completed ones, whose names match a query
+    Search {
+        query: String,
+        /// How to match the query against task names
+        #[arg(long, value_enum, default_value_t = SearchMode::Substring)]
+        mode: SearchMode,
+    },
+    /// Show the number of open tasks for each tag
+    Tags,
+    /// Show tasks created and completed over time, time to complete, streaks and open tasks by priority and tag
+    Stats {
+        /// Count tasks per day or per week
+        #[arg(long, value_enum, default_value_t = Period::Day)]
+        by: Period,
+        /// How many days or weeks to show, 14 days or 8 weeks when not given
+        #[arg(long)]
+        last: Option<usize>,
+    },
+    /// Add a new task to the list. +project and @context words in the name become tags
+    Add {
+        name: String,
+        /// Due date, e.g. 2024-05-01, "tomorrow 09:00" or "in 3 days"
+        #[arg(long)]
+        due: Option<String>,
+        #[arg(long, value_enum, default_value_t = Priority::Normal)]
+        priority: Priority,
+        /// Tag to add, e.g. +work or @phone. Can be repeated
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        /// Add the task as a subtask of the task with this id
+        #[arg(long)]
+        parent: Option<u32>,
+        /// Repeat the task, e.g. daily, "weekly on mon thu", monthly or "every 3 days"
+        #[arg(long)]
+        repeat: Option<String>,
+    },
+    /// Mark one or more tasks as completed
+    #[command(alias = "complete")]
+    Done {
+        #[arg(required = true)]
+        ids: Vec<u32>,
+        /// Also complete all of the tasks' subtasks
+        #[arg(long)]
+        all: bool,
+    },
+    /// Change the name, due date, priority, tags, parent or repeat of a task
+    Edit {
+        id: u32,
+        name: Option<String>,
+        /// New due date, e.g. 2024-05-01, "tomorrow 09:00" or "in 3 days"
+        #[arg(long)]
+        due: Option<String>,
+        /// Remove the due date
+        #[arg(long, conflicts_with = "due")]
+        no_due: bool,
+        #[arg(long, value_enum)]
+        priority: Option<Priority>,
+        /// Tag to add. Can be repeated
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        /// Tag to remove. Can be repeated
+        #[arg(long = "untag")]
+        untags: Vec<String>,
+        /// Move the task under the task with this id
+        #[arg(long)]
+        parent: Option<u32>,
+        /// Move the task back to the top level
+        #[arg(long, conflicts_with = "parent")]
+        no_parent: bool,
+        /// Repeat the task, e.g. daily, "weekly on mon thu", monthly or "every 3 days"
+        #[arg(long)]
+        repeat: Option<String>,
+        /// Stop the task repeating
+        #[arg(long, conflicts_with = "repeat")]
+        no_repeat: bool,
+        /// Replace the task's notes. Use the notes command to write them in $EDITOR instead
+        #[arg(long)]
+        notes: Option<String>,
+        /// Remove the task's notes
+        #[arg(long, conflicts_with = "notes")]
+        no_notes: bool,
+    },
+    /// Reorder a task among the tasks with the same parent. Shows the list in manual order from then on
+    Move {
+        id: u32,
+        /// up, down, top, bottom or a position such as 3
+        to: String,
+    },
+    /// Show everything about a task, including its notes
+    View { id: u32 },
+    /// Edit a task's notes in $EDITOR
+    Notes { id: u32 },
+    /// Start timing a task, stopping the timer of any other task
+    Start { id: u32 },
+    /// Stop the running timer
+    Stop,
+    /// Show the task being timed and how long its timer has been running
+    Timer {
+        /// Keep the timer on screen, updating every second, until interrupted with Ctrl-C
+        #[arg(long, short)]
+        watch: bool,
+    },
+    /// Remove one or more tasks from the list
+    #[command(alias = "delete")]
+    Rm {
+        #[arg(required = true)]
+        ids: Vec<u32>,
+    },
+    /// Move all completed tasks from the list to the archive
+    Clean,
+    /// Show, search, restore or purge tasks moved to the archive by clean
+    Archive {
+        #[command(subcommand)]
+        command: Option<ArchiveCommand>,
+    },
+    /// Remove all tasks from the list
+    Empty {
+        /// Do not ask for confirmation
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Add the tasks from a todo.txt, JSON or Markdown checklist file to the list
+    Import {
+        path: PathBuf,
+        /// File format, worked out from the file extension when not given
+        #[arg(long, value_enum)]
+        format: Option<Format>,
+    },
+    /// Write the task list as todo.txt, JSON or a Markdown checklist
+    Export {
+        #[arg(long, value_enum, default_value_t = Format::Markdown)]
+        format: Format,
+        /// File to write to instead of printing the list
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Write the time tracked against the list's tasks, including archived ones, as CSV
+    Timesheet {
+        /// Total the time for each task, or for each task on each day
+        #[arg(long, value_enum, default_value_t = Timesheet::Task)]
+        by: Timesheet,
+        /// File to write to instead of printing the timesheet
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Show, create, switch, rename or delete lists, or move a task to another list
+    Lists {
+        #[command(subcommand)]
+        command: Option<ListCommand>,
+    },
+    /// Open the full-screen task list
+    Tui,
+    /// Undo the last change to the list
+    Undo,
+    /// Redo the last undone change
+    Redo,
+}
+
+#[derive(Subcommand)]
+pub enum ListCommand {
+    /// Show every list and how many tasks it has
+    Show,
+    /// Add a new empty list
+    Create { name: String },
+    /// Make another list the current one
+    Switch { name: String },
+    Rename { name: String, new_name: String },
+    /// Remove a list and all of its tasks
+    Delete {
+        name: String,
+        /// Do not ask for confirmation
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Move a task and its subtasks from the current list to another one
+    Move {
+        id: u32,
+        #[arg(value_name = "LIST")]
+        target: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ArchiveCommand {
+    /// Show every archived task
+    Show,
+    /// Find archived tasks whose names match a query
+    Search {
+        query: String,
+        #[arg(long, value_enum, default_value_t = SearchMode::Substring)]
+        mode: SearchMode,
+    },
+    /// Put an archived task back in the list
+    Restore { id: u32 },
+    /// Permanently remove archived tasks completed more than some days ago
+    Purge {
+        #[arg(long, default_value_t = archive::DEFAULT_PURGE_DAYS)]
+        days: u32,
+    },
+}
+
+impl Command {
+    //The name recorded in the undo history, or None for commands that are not undoable themselves
+    //or that record their own changes
+    fn action(&self) -> Option<&'static str> {
+        match self {
+            Command::Add { .. } => Some("add"),
+            Command::Done { .. } => Some("complete"),
+            Command::Edit { .. } => Some("edit"),
+            Command::Notes { .. } => Some("notes"),
+            Command::Move { .. } => Some("move"),
+            Command::Start { .. } => Some("start"),
+            Command::Stop => Some("stop"),
+            Command::Rm { .. } => Some("delete"),
+            Command::Empty { .. } => Some("empty"),
+            Command::Import { .. } => Some("import"),
+            _ => None,
+        }
+    }
+}
+
+//Runs a single command against the task list and reports whether the list was changed
+pub fn run(command: Command, list: Option<String>, data: &mut TodoData) -> Result<bool, String> {
+    //--list only switches for this one command
+    let previous = list.as_ref().map(|_| data.current_list.clone());
+    if let Some(list) = &list {
+        if let Command::Lists { .. } = command {
+            return Err(String::from("--list can't be used with the lists command"));
+        }
+        lists::switch_list(data, list)?;
+    }
+
+    let before = data.tasks.clone();
+    let action = command.action();
+    let result = run_command(command, data);
+
+    if let Some(action) = action {
+        data.history.record(action, &data.current_list, before, &data.tasks);
+    }
+    if let Some(previous) = previous {
+        lists::switch_list(data, &previous)?;
+    }
+    result
+}
+
+fn run_command(command: Command, data: &mut TodoData) -> Result<bool, String> {
+    match command {
+        Command::List { sort, tags } => {
+            if let Some(sort) = sort {
+                data.sort = sort;
+            }
+            tasks::show_tasks(&data.tasks, data.sort, &tags::parse_filter(&tags.join(" ")));
+            Ok(sort.is_some())
+        }
+        Command::Due { days, tags } => {
+            tasks::show_due_tasks(&data.tasks, days, &tags::parse_filter(&tags.join(" ")));
+            Ok(false)
+        }
+        Command::Search { query, mode } => {
+            let matches = search::search(&data.tasks, &query, mode)?;
+            search::show_matches(&data.tasks, &matches, data.sort, &query);
+            Ok(false)
+        }
+        Command::Tags => {
+            tags::show_tag_summary(&data.tasks);
+            Ok(false)
+        }
+        Command::Stats { by, last } => {
+            stats::show_stats(&stats::counted_tasks(data), &data.tasks, by, last.unwrap_or(stats::default_periods(by)).max(1));
+            Ok(false)
+        }
+        Command::Add { name, due, priority, tags, parent, repeat } => {
+            let due = due.map(|due| parse_due(&due)).transpose()?;
+            let repeat = repeat.map(|repeat| due::parse_recurrence(&repeat)).transpose()?;
+            if let Some(parent) = parent {
+                task_index(&data.tasks, parent)?;
+            }
+            let id = data.next_id();
+            let task = tasks::add_task(&mut data.tasks, id, name);
+            task.due = due;
+            task.priority = priority;
+            task.parent = parent;
+            task.repeat = repeat;
+            for tag in parse_tags(&tags)? {
+                tags::add_tag(&mut task.tags, tag);
+            }
+            println!("Added task {}", id);
+            Ok(true)
+        }
+        Command::Done { ids, all } => {
+            for &id in &ids {
+                task_index(&data.tasks, id)?;
+            }
+            tasks::complete_tasks(data, &ids, all);
+            Ok(true)
+        }
+        Command::Edit { id, name, due, no_due, priority, tags, untags, parent, no_parent, repeat, no_repeat, notes, no_notes } => {
+            let index = task_index(&data.tasks, id)?;
+            if name.is_none() && due.is_none() && !no_due && priority.is_none() && tags.is_empty() && untags.is_empty() && parent.is_none() && !no_parent && repeat.is_none() && !no_repeat && notes.is_none() && !no_notes {
+                return Err(String::from("Nothing to change, give a new name or one of the options in --help"));
+            }
+
+            if let Some(repeat) = repeat {
+                tasks::set_repeat(&mut data.tasks, index, Some(due::parse_recurrence(&repeat)?));
+            } else if no_repeat {
+                tasks::set_repeat(&mut data.tasks, index, None);
+            }
+
+            if let Some(parent) = parent {
+                task_index(&data.tasks, parent)?;
+                if !tasks::can_be_parent(&data.tasks, index, parent) {
+                    return Err(format!("Task {} can't be moved under itself or one of its subtasks", id));
+                }
+                tasks::set_parent(&mut data.tasks, index, Some(parent));
+            } else if no_parent {
+                tasks::set_parent(&mut data.tasks, index, None);
+            }
+
+            if let Some(due) = due {
+                tasks::set_due(&mut data.tasks, index, Some(parse_due(&due)?));
+            } else if no_due {
+                tasks::set_due(&mut data.tasks, index, None);
+            }
+            if let Some(priority) = priority {
+                tasks::set_priority(&mut data.tasks, index, priority);
+            }
+            if !tags.is_empty() || !untags.is_empty() {
+                let mut task_tags = data.tasks[index].tags.clone();
+                for tag in parse_tags(&tags)? {
+                    tags::add_tag(&mut task_tags, tag);
+                }
+                let untags = parse_tags(&untags)?;
+                task_tags.retain(|tag| !untags.contains(tag));
+                tasks::set_tags(&mut data.tasks, index, task_tags);
+            }
+            if notes.is_some() || no_notes {
+                tasks::set_notes(&mut data.tasks, index, notes);
+            }
+            if let Some(name) = name {
+                tasks::edit_task(&mut data.tasks, index, name);
+            }
+            Ok(true)
+        }
+        Command::Move { id, to } => {
+            let index = task_index(&data.tasks, id)?;
+            let to = tasks::parse_move(&to)?;
+            let position = tasks::move_task(&mut data.tasks, index, to);
+            data.sort = SortOrder::Manual;
+            println!("Moved task {} to position {}", id, position);
+            Ok(true)
+        }
+        Command::View { id } => {
+            tasks::show_task_details(&data.tasks, task_index(&data.tasks, id)?);
+            Ok(false)
+        }
+        Command::Notes { id } => {
+            let index = task_index(&data.tasks, id)?;
+            match edit_notes(data.tasks[index].notes.as_deref())? {
+                Some(notes) => {
+                    tasks::set_notes(&mut data.tasks, index, Some(notes));
+                    println!("Saved notes for task {}", id);
+                    Ok(true)
+                }
+                None => {
+                    println!("Notes were not changed");
+                    Ok(false)
+                }
+            }
+        }
+        Command::Start { id } => {
+            let index = task_index(&data.tasks, id)?;
+            if let Some((name, elapsed)) = timer::start(data, index)? {
+                println!("Stopped timing {} after {}", name, timer::format_clock(elapsed));
+            }
+            println!("Started timing task {}", id);
+            Ok(true)
+        }
+        Command::Stop => match timer::stop(data) {
+            Some((name, elapsed)) => {
+                println!("Stopped timing {} after {}", name, timer::format_clock(elapsed));
+                Ok(true)
+            }
+            None => {
+                println!("No timer is running");
+                Ok(false)
+            }
+        },
+        Command::Timer { watch } => {
+            match timer::active_task(data) {
+                Some(task) if watch => timer::watch_timer(task).map_err(|err| format!("Could not show the timer: {}", err))?,
+                Some(task) => println!("{}", timer::timer_line(task)),
+                None => println!("No timer is running"),
+            }
+            Ok(false)
+        }
+        Command::Rm { ids } => {
+            for &id in &ids {
+                task_index(&data.tasks, id)?;
+            }
+            tasks::delete_tasks(&mut data.tasks, &ids);
+            Ok(true)
+        }
+        Command::Clean => {
+            let count = archive::clean(data);
+            println!("Archived {} completed tasks", count);
+            Ok(count > 0)
+        }
+        Command::Archive { command } => run_archive_command(command.unwrap_or(ArchiveCommand::Show), data),
+        Command::Empty { yes } => {
+            if !yes && !confirm("Remove every task from the list?") {
+                println!("No tasks were removed");
+                return Ok(false);
+            }
+            tasks::clear_tasks(&mut data.tasks);
+            println!("All tasks removed");
+            Ok(true)
+        }
+        Command::Import { path, format } => {
+            let format = format
+                .or_else(|| Format::from_path(&path))
+                .ok_or(format!("Could not tell the format of {}, use --format", path.display()))?;
+            let contents = fs::read_to_string(&path).map_err(|err| format!("Could not read {}: {}", path.display(), err))?;
+            let imported = formats::import(&contents, format)?;
+            let count = formats::add_imported(data, imported);
+            println!("Imported {} tasks", count);
+            Ok(count > 0)
+        }
+        Command::Export { format, output } => {
+            let exported = formats::export(&data.tasks, format);
+            match output {
+                Some(path) => fs::write(&path, exported).map_err(|err| format!("Could not write {}: {}", path.display(), err))?,
+                None => print!("{}", exported),
+            }
+            Ok(false)
+        }
+        Command::Timesheet { by, output } => {
+            let timesheet = timer::export_csv(&stats::counted_tasks(data), &data.current_list, by);
+            match output {
+                Some(path) => fs::write(&path, timesheet).map_err(|err| format!("Could not write {}: {}", path.display(), err))?,
+                None => print!("{}", timesheet),
+            }
+            Ok(false)
+        }
+        Command::Lists { command } => run_list_command(command.unwrap_or(ListCommand::Show), data),
+        Command::Tui => unreachable!("the full-screen view is started from main"),
+        Command::Undo => Ok(history::undo_last_change(data)),
+        Command::Redo => Ok(history::redo_last_change(data)),
+    }
+}
+
+fn run_archive_command(command: ArchiveCommand, data: &mut TodoData) -> Result<bool, String> {
+    match command {
+        ArchiveCommand::Show => {
+            archive::show_archive(&data.archive);
+            Ok(false)
+        }
+        ArchiveCommand::Search { query, mode } => {
+            archive::search_archive(&data.archive, &query, mode)?;
+            Ok(false)
+        }
+        ArchiveCommand::Restore { id } => {
+            archive::restore(data, id)?;
+            println!("Restored task {}", id);
+            Ok(true)
+        }
+        ArchiveCommand::Purge { days } => {
+            let count = archive::purge(data, days);
+            println!("Purged {} archived tasks", count);
+            Ok(count > 0)
+        }
+    }
+}
+
+fn run_list_command(command: ListCommand, data: &mut TodoData) -> Result<bool, String> {
+    match command {
+        ListCommand::Show => {
+            lists::show_lists(data);
+            Ok(false)
+        }
+        ListCommand::Create { name } => {
+            lists::create_list(data, &name)?;
+            println!("Created list {}", name.trim());
+            Ok(true)
+        }
+        ListCommand::Switch { name } => {
+            lists::switch_list(data, &name)?;
+            println!("Switched to list {}", name);
+            Ok(true)
+        }
+        ListCommand::Rename { name, new_name } => {
+            lists::rename_list(data, &name, &new_name)?;
+            println!("Renamed list {} to {}", name, new_name.trim());
+            Ok(true)
+        }
+        ListCommand::Delete { name, yes } => {
+            lists::check_deletable(data, &name)?;
+            let count = lists::list_tasks(data, &name).unwrap().len();
+            if count > 0 && !yes && !confirm(&format!("Delete list {} and its {} tasks?", name, count)) {
+                println!("No lists were deleted");
+                return Ok(false);
+            }
+            lists::delete_list(data, &name)?;
+            println!("Deleted list {}", name);
+            Ok(true)
+        }
+        ListCommand::Move { id, target } => {
+            let index = task_index(&data.tasks, id)?;
+            lists::move_task(data, index, &target)?;
+            println!("Moved task {} to {}", id, target);
+            Ok(true)
+        }
+    }
+}
+
+//Without a terminal to ask on, nothing is removed
+fn confirm(prompt: &str) -> bool {
+    Confirm::new()
+        .with_prompt(prompt)
+        .default(false)
+        .interact()
+        .unwrap_or(false)
+}
+
+//Opens $VISUAL or $EDITOR on the notes. Returns None when the file was closed without saving
+pub fn edit_notes(current: Option<&str>) -> Result<Option<String>, String> {
+    Editor::new().edit(current.unwrap_or_default()).map_err(|err| format!("Could not open an editor: {}", err))
+}
+
+fn parse_due(input: &str) -> Result<Due, String> {
+    due::parse_due(input, due::now().date())
+}
+
+fn parse_tags(input: &[String]) -> Result<Vec<String>, String> {
+    input
+        .iter()
+        .map(|tag| tags::normalize_tag(tag).ok_or(format!("\"{}\" is not a valid tag", tag)))
+        .collect()
+}
+
+fn task_index(tasks: &[Task], id: u32) -> Result<usize, String> {
+    tasks::find_task(tasks, id).ok_or(format!("There is no task with id {}", id))
+}
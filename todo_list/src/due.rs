@@ -0,0 +1,210 @@
+use chrono::{Datelike, Days, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Due {
+    pub date: NaiveDate,
+    pub time: Option<NaiveTime>,
+}
+
+#[derive(PartialEq)]
+pub enum DueStatus {
+    Overdue,
+    Today,
+    Upcoming,
+}
+
+impl Due {
+    //A due date without a time is not overdue until the day is over
+    pub fn status(&self, now: NaiveDateTime) -> DueStatus {
+        let today = now.date();
+        if self.date < today {
+            DueStatus::Overdue
+        } else if self.date > today {
+            DueStatus::Upcoming
+        } else {
+            match self.time {
+                Some(time) if time < now.time() => DueStatus::Overdue,
+                _ => DueStatus::Today,
+            }
+        }
+    }
+
+    pub fn is_within(&self, today: NaiveDate, days: u32) -> bool {
+        match today.checked_add_days(Days::new(days as u64)) {
+            Some(last_day) => self.date <= last_day,
+            None => true,
+        }
+    }
+}
+
+impl fmt::Display for Due {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.time {
+            Some(time) => write!(f, "{} {}", self.date.format("%Y-%m-%d"), time.format("%H:%M")),
+            None => write!(f, "{}", self.date.format("%Y-%m-%d")),
+        }
+    }
+}
+
+pub fn now() -> NaiveDateTime {
+    Local::now().naive_local()
+}
+
+//Accepts ISO dates ("2024-05-01"), "today", "tomorrow", weekday names ("friday", "next friday")
+//and "in N days/weeks/months", each optionally followed by a time such as "14:30"
+pub fn parse_due(input: &str, today: NaiveDate) -> Result<Due, String> {
+    let input = input.trim().to_lowercase();
+    let mut words: Vec<&str> = input.split_whitespace().collect();
+
+    let time = match words.last().and_then(|word| NaiveTime::parse_from_str(word, "%H:%M").ok()) {
+        Some(time) => {
+            words.pop();
+            Some(time)
+        }
+        None => None,
+    };
+
+    if words.is_empty() {
+        return match time {
+            Some(_) => Ok(Due { date: today, time }),
+            None => Err(String::from("Enter a date such as 2024-05-01, tomorrow or in 3 days")),
+        };
+    }
+
+    let date = parse_date(&words, today).ok_or(format!("Could not understand the date \"{}\"", input))?;
+    Ok(Due { date, time })
+}
+
+fn parse_date(words: &[&str], today: NaiveDate) -> Option<NaiveDate> {
+    match words {
+        ["today"] => Some(today),
+        ["tomorrow"] => today.succ_opt(),
+        [day] | ["next", day] if day.parse::<Weekday>().is_ok() => {
+            let weekday = day.parse::<Weekday>().unwrap();
+            let days_ahead = (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+            let days_ahead = if days_ahead == 0 { 7 } else { days_ahead };
+            today.checked_add_days(Days::new(days_ahead as u64))
+        }
+        ["in", amount, unit] => {
+            let amount: u32 = amount.parse().ok()?;
+            match unit.trim_end_matches('s') {
+                "day" => today.checked_add_days(Days::new(amount as u64)),
+                "week" => today.checked_add_days(Days::new(amount as u64 * 7)),
+                "month" => today.checked_add_months(Months::new(amount)),
+                _ => None,
+            }
+        }
+        [date] => NaiveDate::parse_from_str(date, "%Y-%m-%d").ok(),
+        _ => None,
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Recurrence {
+    Daily,
+    //An empty list repeats on the same weekday as the task was due
+    Weekly(Vec<Weekday>),
+    Monthly,
+    EveryDays(u32),
+}
+
+impl Recurrence {
+    pub fn next_after(&self, date: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            Recurrence::Daily => date.succ_opt(),
+            Recurrence::EveryDays(days) => date.checked_add_days(Days::new(*days as u64)),
+            Recurrence::Monthly => date.checked_add_months(Months::new(1)),
+            Recurrence::Weekly(weekdays) if weekdays.is_empty() => date.checked_add_days(Days::new(7)),
+            Recurrence::Weekly(weekdays) => (1..=7)
+                .filter_map(|days| date.checked_add_days(Days::new(days)))
+                .find(|next| weekdays.contains(&next.weekday())),
+        }
+    }
+
+    //Skips occurrences that have already passed, so finishing an overdue daily task schedules it for tomorrow
+    //Monthly repeats count whole months from the original date so the 31st doesn't drift to the 28th
+    pub fn next_due(&self, due: Option<Due>, today: NaiveDate) -> Option<Due> {
+        let base = due.map(|due| due.date).unwrap_or(today);
+        let mut next = self.next_after(base)?;
+        let mut months = 1;
+        while next <= today {
+            next = match self {
+                Recurrence::Monthly => {
+                    months += 1;
+                    base.checked_add_months(Months::new(months))?
+                }
+                _ => self.next_after(next)?,
+            };
+        }
+        Some(Due {
+            date: next,
+            time: due.and_then(|due| due.time),
+        })
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "daily"),
+            Recurrence::Weekly(weekdays) if weekdays.is_empty() => write!(f, "weekly"),
+            Recurrence::Weekly(weekdays) => {
+                let names: Vec<String> = weekdays.iter().map(|weekday| weekday.to_string()).collect();
+                write!(f, "weekly on {}", names.join(" "))
+            }
+            Recurrence::Monthly => write!(f, "monthly"),
+            Recurrence::EveryDays(days) => write!(f, "every {} days", days),
+        }
+    }
+}
+
+//Accepts "daily", "weekly", "weekly on mon thu", "monthly", "every 3 days" and "every 2 weeks"
+pub fn parse_recurrence(input: &str) -> Result<Recurrence, String> {
+    let input = input.trim().to_lowercase();
+    let words: Vec<&str> = input.split([' ', ',']).filter(|word| !word.is_empty()).collect();
+
+    let recurrence = match words.as_slice() {
+        ["daily"] | ["every", "day"] => Some(Recurrence::Daily),
+        ["weekly"] | ["every", "week"] => Some(Recurrence::Weekly(Vec::new())),
+        ["monthly"] | ["every", "month"] => Some(Recurrence::Monthly),
+        ["weekly", days @ ..] | ["every", "week", days @ ..] => {
+            let days = days.strip_prefix(&["on"]).unwrap_or(days);
+            let mut weekdays = Vec::new();
+            for day in days {
+                match day.parse::<Weekday>() {
+                    Ok(weekday) if !weekdays.contains(&weekday) => weekdays.push(weekday),
+                    Ok(_) => {}
+                    Err(_) => return Err(format!("\"{}\" is not a day of the week", day)),
+                }
+            }
+            weekdays.sort_by_key(|weekday| weekday.num_days_from_monday());
+            Some(Recurrence::Weekly(weekdays))
+        }
+        ["every", amount, unit] => match (amount.parse::<u32>(), unit.trim_end_matches('s')) {
+            (Ok(amount), "day") if amount > 0 => Some(Recurrence::EveryDays(amount)),
+            (Ok(amount), "week") if amount > 0 => amount.checked_mul(7).map(Recurrence::EveryDays),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    recurrence.ok_or(String::from("Enter a repeat such as daily, weekly on mon thu, monthly or every 3 days"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeats_in_weeks_are_counted_in_days() {
+        assert_eq!(parse_recurrence("every 3 weeks"), Ok(Recurrence::EveryDays(21)));
+    }
+
+    #[test]
+    fn repeats_too_long_to_count_are_rejected() {
+        assert!(parse_recurrence("every 700000000 weeks").is_err());
+    }
+}
@@ -0,0 +1,381 @@
+use chrono::{DateTime, Local, NaiveDate};
+use clap::ValueEnum;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use crate::due::Due;
+use crate::storage::TodoData;
+use crate::tags;
+use crate::tasks::{self, Priority, Task};
+
+#[derive(ValueEnum, Clone, Copy, PartialEq)]
+pub enum Format {
+    Todotxt,
+    Json,
+    Markdown,
+}
+
+impl Format {
+    pub const ALL: [Format; 3] = [Format::Todotxt, Format::Json, Format::Markdown];
+
+    pub fn from_path(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "txt" => Some(Format::Todotxt),
+            "json" => Some(Format::Json),
+            "md" | "markdown" => Some(Format::Markdown),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Format::Todotxt => write!(f, "todo.txt"),
+            Format::Json => write!(f, "JSON"),
+            Format::Markdown => write!(f, "Markdown"),
+        }
+    }
+}
+
+pub fn export(tasks: &[Task], format: Format) -> String {
+    match format {
+        Format::Todotxt => tasks.iter().map(|task| format!("{}\n", to_todotxt_line(task))).collect(),
+        Format::Json => serde_json::to_string_pretty(tasks).unwrap(),
+        Format::Markdown => {
+            let mut output = String::new();
+            for task in tasks.iter().filter(|task| !tasks.iter().any(|other| Some(other.id) == task.parent)) {
+                write_markdown_tree(tasks, task, 0, &mut output);
+            }
+            output
+        }
+    }
+}
+
+//Imported tasks keep the ids they had in the file, add_imported gives them new ones
+pub fn import(input: &str, format: Format) -> Result<Vec<Task>, String> {
+    match format {
+        Format::Todotxt => input
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .enumerate()
+            .map(|(index, line)| from_todotxt_line(index as u32 + 1, line))
+            .collect(),
+        Format::Json => {
+            let tasks: Vec<Task> = serde_json::from_str(input).map_err(|err| format!("Invalid JSON task list: {}", err))?;
+            Ok(tasks.into_iter().map(clean_json_tags).collect())
+        }
+        Format::Markdown => Ok(from_markdown(input)),
+    }
+}
+
+//A JSON file could have been written by hand, so its tags are checked the same way as typed ones
+fn clean_json_tags(mut task: Task) -> Task {
+    let (name, mut new_tags) = tags::extract_tags(&task.name);
+    for tag in task.tags.iter().filter_map(|tag| tags::normalize_tag(tag)) {
+        tags::add_tag(&mut new_tags, tag);
+    }
+    task.name = name;
+    task.tags = new_tags;
+    task
+}
+
+//Adds imported tasks to the list with fresh ids, keeping subtasks attached to their parents. Parents can only
+//be found again when every task in the file has its own id, otherwise the tasks are all added at the top level
+pub fn add_imported(data: &mut TodoData, imported: Vec<Task>) -> usize {
+    let new_ids: Vec<u32> = imported.iter().map(|_| data.next_id()).collect();
+    let mut ids_in_file: HashMap<u32, u32> = HashMap::new();
+    for (task, new_id) in imported.iter().zip(&new_ids) {
+        ids_in_file.insert(task.id, *new_id);
+    }
+    let unique_ids = ids_in_file.len() == imported.len();
+
+    let mut added: Vec<Task> = Vec::new();
+    for (mut task, new_id) in imported.into_iter().zip(new_ids) {
+        task.id = new_id;
+        task.parent = task.parent.filter(|_| unique_ids).and_then(|parent| ids_in_file.get(&parent).copied());
+        added.push(task);
+    }
+    //A task listed as its own parent, or under one of its own subtasks, is added at the top level instead
+    for index in 0..added.len() {
+        if tasks::in_parent_loop(&added, index) {
+            added[index].parent = None;
+        }
+    }
+
+    let count = added.len();
+    data.tasks.extend(added);
+    count
+}
+
+//todo.txt lines look like "x 2024-05-02 2024-05-01 name +project @context due:2024-05-10"
+//for completed tasks and "(A) 2024-05-01 name" for open ones
+fn to_todotxt_line(task: &Task) -> String {
+    let mut words: Vec<String> = Vec::new();
+    let priority = match task.priority {
+        Priority::Urgent => Some('A'),
+        Priority::High => Some('B'),
+        Priority::Normal => None,
+        Priority::Low => Some('C'),
+    };
+
+    if task.completed {
+        words.push(String::from("x"));
+        words.push(task.completed_at.unwrap_or(task.created).format("%Y-%m-%d").to_string());
+    } else if let Some(priority) = priority {
+        words.push(format!("({})", priority));
+    }
+    words.push(task.created.format("%Y-%m-%d").to_string());
+    words.push(task.name.clone());
+    words.extend(task.tags.iter().cloned());
+    if let Some(due) = task.due {
+        words.push(format!("due:{}", due.date.format("%Y-%m-%d")));
+    }
+    if task.completed {
+        if let Some(priority) = priority {
+            words.push(format!("pri:{}", priority));
+        }
+    }
+
+    words.join(" ")
+}
+
+fn from_todotxt_line(id: u32, line: &str) -> Result<Task, String> {
+    let mut words: Vec<&str> = line.split_whitespace().collect();
+    let mut completed = false;
+    let mut priority = None;
+    let mut dates = Vec::new();
+
+    if words.first() == Some(&"x") {
+        completed = true;
+        words.remove(0);
+    } else if let Some(letter) = words.first().and_then(|word| parse_todotxt_priority(word.strip_prefix('(')?.strip_suffix(')')?)) {
+        priority = Some(letter);
+        words.remove(0);
+    }
+
+    //A completed task can have both a completion and a creation date, an open one only a creation date
+    let max_dates = if completed { 2 } else { 1 };
+    while dates.len() < max_dates {
+        match words.first().and_then(|word| NaiveDate::parse_from_str(word, "%Y-%m-%d").ok()) {
+            Some(date) => {
+                dates.push(date);
+                words.remove(0);
+            }
+            None => break,
+        }
+    }
+
+    let mut name_words = Vec::new();
+    let mut due = None;
+    for word in words {
+        if let Some(date) = word.strip_prefix("due:") {
+            let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| format!("Invalid due date in \"{}\"", line))?;
+            due = Some(Due { date, time: None });
+        } else if let Some(letter) = word.strip_prefix("pri:").and_then(parse_todotxt_priority) {
+            priority = Some(letter);
+        } else {
+            name_words.push(word);
+        }
+    }
+
+    let mut task = Task::new(id, name_words.join(" "));
+    task.completed = completed;
+    task.due = due;
+    task.priority = priority.unwrap_or_default();
+    match (completed, dates.as_slice()) {
+        (true, [completed_on, created_on]) => {
+            task.completed_at = Some(start_of_day(*completed_on));
+            task.created = start_of_day(*created_on);
+        }
+        (true, [completed_on]) => task.completed_at = Some(start_of_day(*completed_on)),
+        (false, [created_on]) => task.created = start_of_day(*created_on),
+        _ => {}
+    }
+    Ok(task)
+}
+
+fn parse_todotxt_priority(letter: &str) -> Option<Priority> {
+    match letter {
+        "A" => Some(Priority::Urgent),
+        "B" => Some(Priority::High),
+        _ if letter.len() == 1 && letter.chars().all(|c| c.is_ascii_uppercase()) => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+fn start_of_day(date: NaiveDate) -> DateTime<Local> {
+    date.and_hms_opt(0, 0, 0).unwrap().and_local_timezone(Local).earliest().unwrap_or_else(Local::now)
+}
+
+//Subtasks are written as nested checkboxes, two spaces deeper than their parent
+fn write_markdown_tree(tasks: &[Task], task: &Task, depth: usize, output: &mut String) {
+    let check = if task.completed { "x" } else { " " };
+    let mut text = task.name.clone();
+    for tag in &task.tags {
+        text.push(' ');
+        text.push_str(tag);
+    }
+    output.push_str(&format!("{}- [{}] {}\n", "  ".repeat(depth), check, text));
+
+    for child in tasks.iter().filter(|child| child.parent == Some(task.id)) {
+        write_markdown_tree(tasks, child, depth + 1, output);
+    }
+}
+
+//Lines that are not checkboxes are ignored, so a whole Markdown document can be imported
+fn from_markdown(input: &str) -> Vec<Task> {
+    let mut tasks: Vec<Task> = Vec::new();
+    let mut parents: Vec<(usize, u32)> = Vec::new();
+
+    for line in input.lines() {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        let item = match trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* ")) {
+            Some(item) => item,
+            None => continue,
+        };
+        let (completed, name) = if let Some(name) = item.strip_prefix("[ ] ") {
+            (false, name)
+        } else if let Some(name) = item.strip_prefix("[x] ").or_else(|| item.strip_prefix("[X] ")) {
+            (true, name)
+        } else {
+            continue;
+        };
+
+        while parents.last().is_some_and(|&(parent_indent, _)| parent_indent >= indent) {
+            parents.pop();
+        }
+
+        let mut task = Task::new(tasks.len() as u32 + 1, name.to_string());
+        task.completed = completed;
+        if completed {
+            task.completed_at = Some(Local::now());
+        }
+        task.parent = parents.last().map(|&(_, id)| id);
+        parents.push((indent, task.id));
+        tasks.push(task);
+    }
+    tasks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_tasks() -> Vec<Task> {
+        let mut release = Task::new(1, String::from("Plan release +work"));
+        release.priority = Priority::Urgent;
+        release.due = Some(Due {
+            date: NaiveDate::from_ymd_opt(2024, 5, 10).unwrap(),
+            time: None,
+        });
+
+        let mut notes = Task::new(2, String::from("Write notes @laptop"));
+        notes.parent = Some(1);
+        notes.completed = true;
+        notes.completed_at = Some(Local::now());
+
+        let mut groceries = Task::new(3, String::from("Buy groceries"));
+        groceries.priority = Priority::Low;
+
+        vec![release, notes, groceries]
+    }
+
+    fn summary(tasks: &[Task]) -> Vec<(String, bool, Vec<String>)> {
+        tasks.iter().map(|task| (task.name.clone(), task.completed, task.tags.clone())).collect()
+    }
+
+    #[test]
+    fn todotxt_round_trip() {
+        let tasks = sample_tasks();
+        let imported = import(&export(&tasks, Format::Todotxt), Format::Todotxt).unwrap();
+
+        assert_eq!(summary(&imported), summary(&tasks));
+        assert_eq!(imported[0].priority, Priority::Urgent);
+        assert_eq!(imported[0].due, tasks[0].due);
+        assert_eq!(imported[2].priority, Priority::Low);
+        assert_eq!(imported[0].created.date_naive(), tasks[0].created.date_naive());
+    }
+
+    #[test]
+    fn todotxt_reads_standard_lines() {
+        let imported = import("x 2024-05-02 2024-05-01 Call mum @phone\n(B) Pay rent +home due:2024-06-01\n", Format::Todotxt).unwrap();
+
+        assert!(imported[0].completed);
+        assert_eq!(imported[0].name, "Call mum");
+        assert_eq!(imported[0].tags, vec![String::from("@phone")]);
+        assert!(!imported[1].completed);
+        assert_eq!(imported[1].priority, Priority::High);
+        assert_eq!(imported[1].due.unwrap().date, NaiveDate::from_ymd_opt(2024, 6, 1).unwrap());
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let tasks = sample_tasks();
+        let imported = import(&export(&tasks, Format::Json), Format::Json).unwrap();
+
+        assert_eq!(imported, tasks);
+    }
+
+    #[test]
+    fn markdown_round_trip() {
+        let tasks = sample_tasks();
+        let exported = export(&tasks, Format::Markdown);
+        let imported = import(&exported, Format::Markdown).unwrap();
+
+        assert_eq!(exported, "- [ ] Plan release +work\n  - [x] Write notes @laptop\n- [ ] Buy groceries\n");
+        assert_eq!(summary(&imported), summary(&tasks));
+        assert_eq!(imported[1].parent, Some(imported[0].id));
+        assert_eq!(imported[2].parent, None);
+    }
+
+    #[test]
+    fn imported_tasks_get_new_ids() {
+        let mut data = TodoData::default();
+        data.next_id();
+        data.next_id();
+
+        add_imported(&mut data, sample_tasks());
+
+        let ids: Vec<u32> = data.tasks.iter().map(|task| task.id).collect();
+        assert_eq!(ids, vec![3, 4, 5]);
+        assert_eq!(data.tasks[1].parent, Some(3));
+    }
+
+    #[test]
+    fn json_without_ids_gets_an_id_per_task() {
+        let mut data = TodoData::default();
+        let input = r#"[{"name":"alpha","completed":false},{"name":"beta","completed":false,"parent":1},{"name":"gamma","completed":true}]"#;
+
+        add_imported(&mut data, import(input, Format::Json).unwrap());
+
+        let ids: Vec<u32> = data.tasks.iter().map(|task| task.id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert!(data.tasks.iter().all(|task| task.parent.is_none()));
+    }
+
+    #[test]
+    fn json_parent_loops_are_broken() {
+        let mut data = TodoData::default();
+        let input = r#"[{"id":1,"name":"loop","completed":false,"parent":1},{"id":2,"name":"a","completed":false,"parent":3},{"id":3,"name":"b","completed":false,"parent":2}]"#;
+
+        add_imported(&mut data, import(input, Format::Json).unwrap());
+
+        let parents: Vec<Option<u32>> = data.tasks.iter().map(|task| task.parent).collect();
+        assert_eq!(parents, vec![None, None, Some(2)]);
+        assert_eq!(tasks::descendants(&data.tasks, 1), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn json_tags_are_normalized() {
+        let input = r#"[{"name":"Call the bank @Phone","completed":false,"tags":["éa","+Work",""]}]"#;
+        let imported = import(input, Format::Json).unwrap();
+
+        assert_eq!(imported[0].name, "Call the bank");
+        assert_eq!(imported[0].tags, vec![String::from("@phone"), String::from("+éa"), String::from("+work")]);
+        assert!(!tags::matches_filter(&imported[0], &tags::parse_filter("a")));
+        assert!(tags::matches_filter(&imported[0], &tags::parse_filter("éa")));
+    }
+}
@@ -0,0 +1,165 @@
+use console::style;
+use serde::{Deserialize, Serialize};
+
+use crate::archive::ArchivedTask;
+use crate::lists;
+use crate::storage::TodoData;
+use crate::tasks::Task;
+
+//Only the most recent changes are kept so the saved file does not grow forever
+const MAX_HISTORY: usize = 50;
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    action: String,
+    //The list the change was made to. Histories saved before there were named lists only had the default list
+    #[serde(default = "lists::default_list_name")]
+    list: String,
+    tasks: Vec<Task>,
+    //Moving a task between lists changes a second list as well
+    #[serde(default)]
+    other_list: Option<(String, Vec<Task>)>,
+    //Cleaning, restoring and purging change the archive as well
+    #[serde(default)]
+    archive: Option<Vec<ArchivedTask>>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct History {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+}
+
+impl History {
+    //Remembers a list from before an action, if the action actually changed anything
+    pub fn record(&mut self, action: &str, list: &str, before: Vec<Task>, after: &[Task]) {
+        if before == after {
+            return;
+        }
+        self.push(Snapshot {
+            action: action.to_string(),
+            list: list.to_string(),
+            tasks: before,
+            other_list: None,
+            archive: None,
+        });
+    }
+
+    //Remembers a list and the archive from before an action that moved tasks between them
+    pub fn record_archive(&mut self, action: &str, list: &str, before: Vec<Task>, archive: Vec<ArchivedTask>) {
+        self.push(Snapshot {
+            action: action.to_string(),
+            list: list.to_string(),
+            tasks: before,
+            other_list: None,
+            archive: Some(archive),
+        });
+    }
+
+    //Remembers both lists from before a task was moved between them, so undo puts it back in one step
+    pub fn record_move(&mut self, action: &str, from: (String, Vec<Task>), to: (String, Vec<Task>)) {
+        self.push(Snapshot {
+            action: action.to_string(),
+            list: from.0,
+            tasks: from.1,
+            other_list: Some(to),
+            archive: None,
+        });
+    }
+
+    //Keeps earlier changes to a renamed list undoable
+    pub fn rename_list(&mut self, name: &str, new_name: &str) {
+        for snapshot in self.undo.iter_mut().chain(self.redo.iter_mut()) {
+            if snapshot.list == name {
+                snapshot.list = new_name.to_string();
+            }
+            if let Some((other, _)) = &mut snapshot.other_list {
+                if other == name {
+                    *other = new_name.to_string();
+                }
+            }
+        }
+    }
+
+    //Drops the changes to a deleted list so undoing them does not bring it back
+    pub fn forget_list(&mut self, name: &str) {
+        let mentions = |snapshot: &Snapshot| snapshot.list == name || snapshot.other_list.as_ref().is_some_and(|(other, _)| other == name);
+        self.undo.retain(|snapshot| !mentions(snapshot));
+        self.redo.retain(|snapshot| !mentions(snapshot));
+    }
+
+    //Snapshots from before someone else's changes were merged in would throw those changes away if put back
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    fn push(&mut self, snapshot: Snapshot) {
+        self.undo.push(snapshot);
+        if self.undo.len() > MAX_HISTORY {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+}
+
+//Puts a snapshot's lists back and returns a snapshot of what they held before, for the opposite stack
+fn restore(data: &mut TodoData, snapshot: Snapshot) -> Snapshot {
+    let tasks = lists::replace_list(data, &snapshot.list, snapshot.tasks);
+    let other_list = snapshot.other_list.map(|(name, other_tasks)| {
+        let replaced = lists::replace_list(data, &name, other_tasks);
+        (name, replaced)
+    });
+    let archive = snapshot.archive.map(|archive| std::mem::replace(&mut data.archive, archive));
+
+    Snapshot {
+        action: snapshot.action,
+        list: snapshot.list,
+        tasks,
+        other_list,
+        archive,
+    }
+}
+
+//Restores the lists from before the last action and returns the name of the action undone
+pub fn undo(data: &mut TodoData) -> Option<String> {
+    let snapshot = data.history.undo.pop()?;
+    let current = restore(data, snapshot);
+    let action = current.action.clone();
+    data.history.redo.push(current);
+    Some(action)
+}
+
+pub fn redo(data: &mut TodoData) -> Option<String> {
+    let snapshot = data.history.redo.pop()?;
+    let current = restore(data, snapshot);
+    let action = current.action.clone();
+    data.history.undo.push(current);
+    Some(action)
+}
+
+pub fn undo_last_change(data: &mut TodoData) -> bool {
+    match undo(data) {
+        Some(action) => {
+            println!("{}", style(format!("Undid {}", action)).blue());
+            true
+        }
+        None => {
+            println!("{}", style("Nothing to undo").blue());
+            false
+        }
+    }
+}
+
+pub fn redo_last_change(data: &mut TodoData) -> bool {
+    match redo(data) {
+        Some(action) => {
+            println!("{}", style(format!("Redid {}", action)).blue());
+            true
+        }
+        None => {
+            println!("{}", style("Nothing to redo").blue());
+            false
+        }
+    }
+}
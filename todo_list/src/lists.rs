@@ -0,0 +1,152 @@
+use console::style;
+
+use crate::storage::TodoData;
+use crate::tasks;
+use crate::tasks::Task;
+
+pub const DEFAULT_LIST: &str = "default";
+
+pub fn default_list_name() -> String {
+    String::from(DEFAULT_LIST)
+}
+
+//Every list name, in alphabetical order
+pub fn list_names(data: &TodoData) -> Vec<String> {
+    let mut names: Vec<String> = data.lists.keys().cloned().collect();
+    names.push(data.current_list.clone());
+    names.sort();
+    names
+}
+
+pub fn list_tasks<'a>(data: &'a TodoData, name: &str) -> Option<&'a [Task]> {
+    if name == data.current_list {
+        Some(&data.tasks)
+    } else {
+        data.lists.get(name).map(|tasks| tasks.as_slice())
+    }
+}
+
+pub fn show_lists(data: &TodoData) {
+    for name in list_names(data) {
+        let tasks = list_tasks(data, &name).unwrap();
+        let open = tasks.iter().filter(|task| !task.completed).count();
+        let line = format!("{} ({} open, {} total)", name, open, tasks.len());
+        if name == data.current_list {
+            println!("{} {}", style("*").green(), style(line).green().bold());
+        } else {
+            println!("  {}", line);
+        }
+    }
+}
+
+//Swaps in new tasks for a list and returns the tasks it held
+pub fn replace_list(data: &mut TodoData, name: &str, tasks: Vec<Task>) -> Vec<Task> {
+    if name == data.current_list {
+        std::mem::replace(&mut data.tasks, tasks)
+    } else {
+        data.lists.insert(name.to_string(), tasks).unwrap_or_default()
+    }
+}
+
+fn check_new_name(data: &TodoData, name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err(String::from("A list needs a name"));
+    }
+    if name == data.current_list || data.lists.contains_key(name) {
+        return Err(format!("There is already a list called {}", name));
+    }
+    Ok(())
+}
+
+fn check_exists(data: &TodoData, name: &str) -> Result<(), String> {
+    if name != data.current_list && !data.lists.contains_key(name) {
+        return Err(format!("There is no list called {}", name));
+    }
+    Ok(())
+}
+
+pub fn create_list(data: &mut TodoData, name: &str) -> Result<(), String> {
+    let name = name.trim();
+    check_new_name(data, name)?;
+    data.lists.insert(name.to_string(), Vec::new());
+    Ok(())
+}
+
+pub fn switch_list(data: &mut TodoData, name: &str) -> Result<(), String> {
+    check_exists(data, name)?;
+    if name == data.current_list {
+        return Ok(());
+    }
+
+    let tasks = data.lists.remove(name).unwrap();
+    let previous = std::mem::replace(&mut data.tasks, tasks);
+    let previous_name = std::mem::replace(&mut data.current_list, name.to_string());
+    data.lists.insert(previous_name, previous);
+    Ok(())
+}
+
+pub fn rename_list(data: &mut TodoData, name: &str, new_name: &str) -> Result<(), String> {
+    let new_name = new_name.trim();
+    check_exists(data, name)?;
+    check_new_name(data, new_name)?;
+
+    if name == data.current_list {
+        data.current_list = new_name.to_string();
+    } else {
+        let tasks = data.lists.remove(name).unwrap();
+        data.lists.insert(new_name.to_string(), tasks);
+    }
+    for entry in data.archive.iter_mut().filter(|entry| entry.list == name) {
+        entry.list = new_name.to_string();
+    }
+    data.history.rename_list(name, new_name);
+    Ok(())
+}
+
+//The current list can't be deleted, switch to another one first
+pub fn check_deletable(data: &TodoData, name: &str) -> Result<(), String> {
+    check_exists(data, name)?;
+    if name == data.current_list {
+        return Err(String::from("Switch to another list before deleting this one"));
+    }
+    Ok(())
+}
+
+pub fn delete_list(data: &mut TodoData, name: &str) -> Result<(), String> {
+    check_deletable(data, name)?;
+    data.lists.remove(name);
+    data.history.forget_list(name);
+    Ok(())
+}
+
+//Moves a task and its subtasks from the current list to the end of another one
+pub fn move_task(data: &mut TodoData, index: usize, target: &str) -> Result<(), String> {
+    check_exists(data, target)?;
+    if target == data.current_list {
+        return Err(format!("The task is already in {}", target));
+    }
+
+    let before = data.tasks.clone();
+    let target_before = data.lists[target].clone();
+
+    let mut moved: Vec<u32> = tasks::descendants(&data.tasks, data.tasks[index].id).iter().map(|&child| data.tasks[child].id).collect();
+    moved.insert(0, data.tasks[index].id);
+
+    let root = data.tasks[index].id;
+    let mut moving: Vec<Task> = Vec::new();
+    data.tasks.retain(|task| {
+        if moved.contains(&task.id) {
+            moving.push(task.clone());
+            false
+        } else {
+            true
+        }
+    });
+    for task in moving.iter_mut().filter(|task| task.id == root) {
+        task.parent = None;
+    }
+    data.lists.get_mut(target).unwrap().extend(moving);
+
+    data.history.record_move("move to list", (data.current_list.clone(), before), (target.to_string(), target_before));
+    Ok(())
+}
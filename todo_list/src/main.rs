@@ -1,104 +1,722 @@
+mod archive;
+mod cli;
+mod due;
+mod formats;
+mod history;
+mod lists;
+mod merge;
+mod search;
+mod stats;
+mod storage;
+mod tags;
+mod tasks;
+mod timer;
+mod tui;
+
+use clap::Parser;
+use dialoguer::Confirm;
 use dialoguer::Input;
 use dialoguer::FuzzySelect;
-use console::Style;
+use dialoguer::MultiSelect;
 use console::style;
-use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
 
-struct Task {
-    name: String,
-    completed: bool
-}
+use crate::cli::{Cli, Command};
+use crate::due::{Due, Recurrence};
+use crate::formats::Format;
+use crate::search::{SearchMatch, SearchMode};
+use crate::stats::Period;
+use crate::storage::{Store, TodoData};
+use crate::tasks::{Move, Priority, SortOrder, Task};
+use crate::timer::Timesheet;
 
-impl fmt::Display for Task {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.name)
+fn main() {
+    let cli = Cli::parse();
+    let (mut store, mut data) = match cli.file.clone() {
+        Some(path) => Store::open(path, true),
+        None => Store::open(storage::default_path(), false),
     }
-}
+    .unwrap_or_else(|err| {
+        eprintln!("{}", style(format!("Failed to load saved tasks: {}", err)).red());
+        process::exit(1);
+    });
 
-fn main() {
-    let mut tasks: Vec<Task> = Vec::new();
-    const OPERATIONS: [&str; 9] = ["show","add","complete","edit","delete","clean","empty","help","exit"];
+    //The menu and full-screen view stay on the list given with --list, single commands switch back afterwards
+    if let (Some(list), None | Some(Command::Tui)) = (&cli.list, &cli.command) {
+        if let Err(message) = lists::switch_list(&mut data, list) {
+            eprintln!("{}", style(message).red());
+            process::exit(1);
+        }
+        save(&mut store, &mut data);
+    }
+
+    if let Some(Command::Tui) = cli.command {
+        tui::run(&mut data, &mut store).expect("Failed to run the full-screen view");
+        return;
+    }
+
+    //Run a single command and exit when one is given, otherwise fall back to the interactive menu
+    if let Some(command) = cli.command {
+        match cli::run(command, cli.list, &mut data) {
+            Ok(modified) => {
+                if modified {
+                    save(&mut store, &mut data);
+                }
+            }
+            Err(message) => {
+                eprintln!("{}", style(message).red());
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    const OPERATIONS: [&str; 30] = ["show","tui","sort","due","filter","search","tags","stats","lists","add","subtask","complete","edit","move","view","notes","start","stop","delete","bulk","clean","archive","empty","undo","redo","import","export","timesheet","help","exit"];
+
+    const SELF_RECORDING: [&str; 8] = ["undo","redo","tui","lists","search","bulk","clean","archive"];
+
+    //Tags the menu is currently restricted to, for this session only
+    let mut filter: Vec<String> = Vec::new();
 
     loop {
         //Get the current command from the user
-        let command_input = FuzzySelect::new().with_prompt("Choose an operation").items(&OPERATIONS).interact().unwrap();
-
-        match command_input {
-            0 => show_tasks(&tasks),
-            1 => add_task(&mut tasks),
-            2 => complete_task(&mut tasks),
-            3 => edit_task(&mut tasks),
-            4 => delete_task(&mut tasks),
-            5 => remove_completed_tasks(&mut tasks),
-            6 => clear_tasks(&mut tasks),
-            7 => help(),
-            8 => break,
-            9_usize.. => panic!("Invalid operator supplied"),
+        let mut details = vec![format!("list: {}", data.current_list)];
+        if let Some(task) = timer::active_task(&data) {
+            details.push(format!("timing: {} {}", task.name, timer::format_clock(timer::running(task).unwrap())));
+        }
+        if !filter.is_empty() {
+            details.push(format!("filter: {}", filter.join(" ")));
+        }
+        let prompt = format!("Choose an operation ({})", details.join(", "));
+        let command_input = FuzzySelect::new().with_prompt(prompt).items(&OPERATIONS).interact().unwrap();
+
+        //Pick up anything others have saved to a shared file since the last operation
+        match store.refresh(&mut data) {
+            Ok(Some(message)) => println!("{}", style(message).blue()),
+            Ok(None) => {}
+            Err(err) => {
+                eprintln!("{}", style(format!("Failed to load saved tasks: {}", err)).red());
+                process::exit(1);
+            }
+        }
+
+        let (list, before) = (data.current_list.clone(), data.tasks.clone());
+        let modified = match command_input {
+            0 => { tasks::show_tasks(&data.tasks, data.sort, &filter); false },
+            1 => {
+                tui::run(&mut data, &mut store).expect("Failed to run the full-screen view");
+                false
+            },
+            2 => {
+                let current = SortOrder::ALL.iter().position(|sort| *sort == data.sort).unwrap();
+                let selected = FuzzySelect::new().with_prompt("Sort tasks by").items(&SortOrder::ALL).default(current).interact().unwrap();
+                data.sort = SortOrder::ALL[selected];
+                tasks::show_tasks(&data.tasks, data.sort, &filter);
+                true
+            },
+            3 => {
+                let days = Input::new().with_prompt("Show tasks due within how many days?").default(7).interact_text().unwrap();
+                tasks::show_due_tasks(&data.tasks, days, &filter);
+                false
+            },
+            4 => {
+                let input: String = Input::new().with_prompt("Only show tasks tagged (e.g. +work @phone, empty to show all)").with_initial_text(filter.join(" ")).allow_empty(true).interact_text().unwrap();
+                filter = tags::parse_filter(&input);
+                tasks::show_tasks(&data.tasks, data.sort, &filter);
+                false
+            },
+            5 => {
+                let mode = prompt_search_mode();
+                let query: String = Input::new().with_prompt("Search for").interact_text().unwrap();
+                match search::search(&data.tasks, &query, mode) {
+                    Ok(matches) => {
+                        search::show_matches(&data.tasks, &matches, data.sort, &query);
+                        match act_on_match(&mut data, &matches) {
+                            Some(action) => { data.history.record(action, &list, before.clone(), &data.tasks); true },
+                            None => false,
+                        }
+                    },
+                    Err(message) => { println!("{}", style(message).red()); false },
+                }
+            },
+            6 => { tags::show_tag_summary(&data.tasks); false },
+            7 => {
+                let selected = FuzzySelect::new().with_prompt("Count tasks per").items(&Period::ALL).default(0).interact().unwrap();
+                let period = Period::ALL[selected];
+                stats::show_stats(&stats::counted_tasks(&data), &data.tasks, period, stats::default_periods(period));
+                false
+            },
+            8 => manage_lists(&mut data, &filter),
+            9 => { add_task(&mut data, None); true },
+            10 => match pick_task(&data.tasks, &filter, "Which task would you like to add a subtask to?") {
+                Some(index) => {
+                    let parent = data.tasks[index].id;
+                    add_task(&mut data, Some(parent));
+                    true
+                },
+                None => false,
+            },
+            11 => match pick_task(&data.tasks, &filter, "Which task would you like to complete?") {
+                Some(index) => { complete_task(&mut data, index); true },
+                None => false,
+            },
+            12 => match pick_task(&data.tasks, &filter, "Which task would you like to edit?") {
+                Some(index) => { edit_task(&mut data, index); true },
+                None => false,
+            },
+            13 => match pick_task(&data.tasks, &filter, "Which task would you like to move?") {
+                Some(index) => {
+                    let name = data.tasks[index].name.clone();
+                    let position = tasks::move_task(&mut data.tasks, index, prompt_move());
+                    data.sort = SortOrder::Manual;
+                    println!("{}", style(format!("Moved {} to position {}", name, position)).blue());
+                    true
+                },
+                None => false,
+            },
+            14 => match pick_task(&data.tasks, &filter, "Which task would you like to view?") {
+                Some(index) => { tasks::show_task_details(&data.tasks, index); false },
+                None => false,
+            },
+            15 => match pick_task(&data.tasks, &filter, "Which task's notes would you like to edit?") {
+                Some(index) => edit_notes(&mut data, index),
+                None => false,
+            },
+            16 => match pick_task(&data.tasks, &filter, "Which task would you like to start timing?") {
+                Some(index) => start_timer(&mut data, index),
+                None => false,
+            },
+            17 => match timer::stop(&mut data) {
+                Some((name, elapsed)) => {
+                    println!("{}", style(format!("Stopped timing {} after {}", name, timer::format_clock(elapsed))).blue());
+                    true
+                },
+                None => { println!("{}", style("No timer is running").blue()); false },
+            },
+            18 => match pick_task(&data.tasks, &filter, "Which task would you like to delete?") {
+                Some(index) => { tasks::delete_task(&mut data.tasks, index); true },
+                None => false,
+            },
+            19 => match bulk_change(&mut data, &filter) {
+                Some(action) => { data.history.record(action, &list, before.clone(), &data.tasks); true },
+                None => false,
+            },
+            20 => {
+                let count = archive::clean(&mut data);
+                println!("{}", style(format!("Archived {} completed tasks", count)).blue());
+                count > 0
+            },
+            21 => manage_archive(&mut data),
+            22 => {
+                if Confirm::new().with_prompt("Remove every task from the list?").default(false).interact().unwrap() {
+                    tasks::clear_tasks(&mut data.tasks);
+                    println!("{}", style("All tasks removed").blue());
+                    true
+                } else {
+                    false
+                }
+            },
+            23 => history::undo_last_change(&mut data),
+            24 => history::redo_last_change(&mut data),
+            25 => import_tasks(&mut data),
+            26 => { export_tasks(&data.tasks); false },
+            27 => { export_timesheet(&data); false },
+            28 => { help(); false },
+            29 => break,
+            30_usize.. => panic!("Invalid operator supplied"),
+        };
+
+        //Undo and redo move through the history rather than adding to it, and the other operations here record their own changes
+        let operation = OPERATIONS[command_input];
+        if !SELF_RECORDING.contains(&operation) {
+            data.history.record(operation, &list, before, &data.tasks);
+        }
+
+        //Save after every change so nothing is lost when the program exits
+        if modified {
+            save(&mut store, &mut data);
         }
     }
 }
 
-fn show_tasks(tasks: &Vec<Task>) {
-    if tasks.len() == 0 {
-        println!("{}", style("You have no tasks").blue());
-        return;
+//Changes someone else made to a shared file are merged in when saving, so say so
+fn save(store: &mut Store, data: &mut TodoData) {
+    match store.save(data) {
+        Ok(Some(message)) => println!("{}", style(message).blue()),
+        Ok(None) => {}
+        Err(err) => {
+            eprintln!("{}", style(format!("Failed to save tasks: {}", err)).red());
+            process::exit(1);
+        }
+    }
+}
+
+fn add_task(data: &mut TodoData, parent: Option<u32>) {
+    let name = Input::new().with_prompt("Enter task name").interact_text().unwrap();
+    let due = prompt_due(None);
+    let priority = prompt_priority(Priority::Normal);
+    let extra_tags = prompt_tags(&[]);
+    let repeat = prompt_repeat(None);
+    let id = data.next_id();
+    let task = tasks::add_task(&mut data.tasks, id, name);
+    task.due = due;
+    task.priority = priority;
+    task.parent = parent;
+    task.repeat = repeat;
+    for tag in extra_tags {
+        tags::add_tag(&mut task.tags, tag);
+    }
+}
+
+//Completes, deletes, re-tags or re-prioritises several tasks at once. Returns the action taken, if any
+fn bulk_change(data: &mut TodoData, filter: &[String]) -> Option<&'static str> {
+    const ACTIONS: [&str; 5] = ["complete", "delete", "add tags", "remove tags", "set priority"];
+    let ids = pick_tasks(&data.tasks, filter)?;
+    let action = FuzzySelect::new().with_prompt(format!("What would you like to do with these {} tasks?", ids.len())).items(&ACTIONS).default(0).interact_opt().unwrap()?;
+
+    let indexes: Vec<usize> = ids.iter().filter_map(|&id| tasks::find_task(&data.tasks, id)).collect();
+    match action {
+        0 => tasks::complete_tasks(data, &ids, false),
+        1 => {
+            if !Confirm::new().with_prompt(format!("Delete {} tasks and their subtasks?", ids.len())).default(false).interact().unwrap() {
+                return None;
+            }
+            tasks::delete_tasks(&mut data.tasks, &ids);
+        },
+        2 | 3 => {
+            let changed = prompt_tags(&[]);
+            for index in indexes {
+                let mut task_tags = data.tasks[index].tags.clone();
+                if action == 2 {
+                    for tag in changed.iter().cloned() {
+                        tags::add_tag(&mut task_tags, tag);
+                    }
+                } else {
+                    task_tags.retain(|tag| !changed.contains(tag));
+                }
+                tasks::set_tags(&mut data.tasks, index, task_tags);
+            }
+        },
+        4 => {
+            let priority = prompt_priority(Priority::Normal);
+            for index in indexes {
+                tasks::set_priority(&mut data.tasks, index, priority);
+            }
+        },
+        5_usize.. => panic!("Invalid operator supplied"),
+    }
+    println!("{}", style(format!("Changed {} tasks", ids.len())).blue());
+    Some(match action {
+        0 => "bulk complete",
+        1 => "bulk delete",
+        4 => "bulk set priority",
+        _ => "bulk retag",
+    })
+}
+
+//Offers the tasks matching the filter with check boxes. Typing a pattern first ticks every task whose name contains it
+fn pick_tasks(tasks: &[Task], filter: &[String]) -> Option<Vec<u32>> {
+    let matching: Vec<&Task> = tasks.iter().filter(|task| tags::matches_filter(task, filter)).collect();
+    if matching.is_empty() {
+        println!("{}", style("No tasks match the filter").blue());
+        return None;
+    }
+
+    let pattern: String = Input::new().with_prompt("Select all tasks matching (empty to pick them yourself)").allow_empty(true).interact_text().unwrap();
+    let pattern = pattern.trim().to_lowercase();
+    let selected: Vec<bool> = matching.iter().map(|task| !pattern.is_empty() && task.name.to_lowercase().contains(&pattern)).collect();
+    let items: Vec<String> = matching.iter().map(|task| if task.completed { format!("{} (done)", task.name) } else { task.name.clone() }).collect();
+
+    let chosen = MultiSelect::new().with_prompt("Which tasks? (space to select, enter to confirm)").items(&items).defaults(&selected).interact_opt().unwrap()?;
+    if chosen.is_empty() {
+        println!("{}", style("No tasks selected").blue());
+        return None;
+    }
+    Some(chosen.iter().map(|&index| matching[index].id).collect())
+}
+
+fn start_timer(data: &mut TodoData, index: usize) -> bool {
+    match timer::start(data, index) {
+        Ok(stopped) => {
+            if let Some((name, elapsed)) = stopped {
+                println!("{}", style(format!("Stopped timing {} after {}", name, timer::format_clock(elapsed))).blue());
+            }
+            println!("{}", style(format!("Started timing {}", data.tasks[index].name)).blue());
+            true
+        },
+        Err(message) => { println!("{}", style(message).red()); false },
+    }
+}
+
+fn complete_task(data: &mut TodoData, index: usize) {
+    if tasks::has_open_subtasks(&data.tasks, index) && Confirm::new().with_prompt("Also complete all of its subtasks?").default(true).interact().unwrap() {
+        tasks::complete_subtasks(data, index);
+    }
+    tasks::complete_task(data, index);
+}
+
+fn edit_task(data: &mut TodoData, index: usize) {
+    let name = Input::new().with_prompt("Enter new task name").with_initial_text(&data.tasks[index].name).interact_text().unwrap();
+    let due = prompt_due(data.tasks[index].due);
+    let priority = prompt_priority(data.tasks[index].priority);
+    let task_tags = prompt_tags(&data.tasks[index].tags);
+    let repeat = prompt_repeat(data.tasks[index].repeat.as_ref());
+    tasks::set_tags(&mut data.tasks, index, task_tags);
+    tasks::set_repeat(&mut data.tasks, index, repeat);
+    tasks::edit_task(&mut data.tasks, index, name);
+    tasks::set_due(&mut data.tasks, index, due);
+    tasks::set_priority(&mut data.tasks, index, priority);
+}
+
+fn edit_notes(data: &mut TodoData, index: usize) -> bool {
+    match cli::edit_notes(data.tasks[index].notes.as_deref()) {
+        Ok(Some(notes)) => {
+            tasks::set_notes(&mut data.tasks, index, Some(notes));
+            println!("{}", style("Notes saved").blue());
+            true
+        }
+        Ok(None) => {
+            println!("{}", style("Notes were not changed").blue());
+            false
+        }
+        Err(message) => {
+            println!("{}", style(message).red());
+            false
+        }
+    }
+}
+
+//Lets the user complete, edit or delete one of the search results. Returns the action taken, if any
+fn act_on_match(data: &mut TodoData, matches: &[SearchMatch]) -> Option<&'static str> {
+    const ACTIONS: [&str; 3] = ["complete", "edit", "delete"];
+    if matches.is_empty() {
+        return None;
+    }
+
+    let items: Vec<&Task> = matches.iter().map(|found| &data.tasks[found.index]).collect();
+    let selected = FuzzySelect::new().with_prompt("Act on a match? (Esc to skip)").items(&items).default(0).interact_opt().unwrap()?;
+    let index = matches[selected].index;
+    let action = FuzzySelect::new().with_prompt("What would you like to do with it?").items(&ACTIONS).default(0).interact_opt().unwrap()?;
+
+    match action {
+        0 if data.tasks[index].completed => {
+            println!("{}", style("That task is already completed").blue());
+            return None;
+        },
+        0 => complete_task(data, index),
+        1 => edit_task(data, index),
+        2 => tasks::delete_task(&mut data.tasks, index),
+        3_usize.. => panic!("Invalid operator supplied"),
     }
+    Some(ACTIONS[action])
+}
 
-    for task in tasks {
-        let style = if task.completed { Style::new().green().strikethrough() } else { Style::new().red() };
-        println!("{}", style.apply_to(&task.name));
+fn manage_archive(data: &mut TodoData) -> bool {
+    const ARCHIVE_OPERATIONS: [&str; 4] = ["show", "search", "restore", "purge"];
+    let selected = FuzzySelect::new().with_prompt("Archive").items(&ARCHIVE_OPERATIONS).default(0).interact().unwrap();
+
+    match selected {
+        0 => { archive::show_archive(&data.archive); false },
+        1 => {
+            let mode = prompt_search_mode();
+            let query: String = Input::new().with_prompt("Search the archive for").interact_text().unwrap();
+            if let Err(message) = archive::search_archive(&data.archive, &query, mode) {
+                println!("{}", style(message).red());
+            }
+            false
+        },
+        2 => {
+            if data.archive.is_empty() {
+                println!("{}", style("The archive is empty").blue());
+                return false;
+            }
+            let items: Vec<&Task> = data.archive.iter().map(|entry| &entry.task).collect();
+            let selected = FuzzySelect::new().with_prompt("Which task would you like to restore?").items(&items).interact().unwrap();
+            let id = data.archive[selected].task.id;
+            archive::restore(data, id).unwrap();
+            println!("{}", style(format!("Restored {}", data.tasks.last().unwrap().name)).blue());
+            true
+        },
+        3 => {
+            let days: u32 = Input::new().with_prompt("Purge tasks completed more than how many days ago?").default(archive::DEFAULT_PURGE_DAYS).interact_text().unwrap();
+            let count = archive::count_older_than(&data.archive, days);
+            if count > 0 && Confirm::new().with_prompt(format!("Permanently remove {} archived tasks?", count)).default(false).interact().unwrap() {
+                archive::purge(data, days);
+                println!("{}", style(format!("Purged {} archived tasks", count)).blue());
+                true
+            } else {
+                println!("{}", style("No archived tasks were purged").blue());
+                false
+            }
+        },
+        4_usize.. => panic!("Invalid operator supplied"),
     }
 }
 
-fn add_task(tasks: &mut Vec<Task>) {
-    let new_task = Input::new().with_prompt("Enter task name").interact_text().unwrap();
-    let new_task: Task = Task {
-        name: new_task,
-        completed: false
+fn manage_lists(data: &mut TodoData, filter: &[String]) -> bool {
+    const LIST_OPERATIONS: [&str; 6] = ["show", "create", "switch", "rename", "delete", "move task"];
+    let selected = FuzzySelect::new().with_prompt("Lists").items(&LIST_OPERATIONS).interact().unwrap();
+    let names = lists::list_names(data);
+
+    let result = match selected {
+        0 => { lists::show_lists(data); Ok(false) },
+        1 => {
+            let name: String = Input::new().with_prompt("Name of the new list").interact_text().unwrap();
+            lists::create_list(data, &name).and_then(|()| lists::switch_list(data, name.trim())).map(|()| true)
+        },
+        2 => {
+            let current = names.iter().position(|name| *name == data.current_list).unwrap();
+            let selected = FuzzySelect::new().with_prompt("Switch to list").items(&names).default(current).interact().unwrap();
+            lists::switch_list(data, &names[selected]).map(|()| true)
+        },
+        3 => {
+            let selected = FuzzySelect::new().with_prompt("Which list would you like to rename?").items(&names).interact().unwrap();
+            let new_name: String = Input::new().with_prompt("Enter new list name").with_initial_text(&names[selected]).interact_text().unwrap();
+            lists::rename_list(data, &names[selected], &new_name).map(|()| true)
+        },
+        4 => {
+            let others: Vec<&String> = names.iter().filter(|name| **name != data.current_list).collect();
+            if others.is_empty() {
+                Err(String::from("There are no other lists to delete"))
+            } else {
+                let selected = FuzzySelect::new().with_prompt("Which list would you like to delete?").items(&others).interact().unwrap();
+                let name = others[selected].clone();
+                let count = lists::list_tasks(data, &name).unwrap().len();
+                if count == 0 || Confirm::new().with_prompt(format!("Delete list {} and its {} tasks?", name, count)).default(false).interact().unwrap() {
+                    lists::delete_list(data, &name).map(|()| true)
+                } else {
+                    Ok(false)
+                }
+            }
+        },
+        5 => {
+            let others: Vec<&String> = names.iter().filter(|name| **name != data.current_list).collect();
+            if others.is_empty() {
+                Err(String::from("Create another list to move tasks to first"))
+            } else {
+                match pick_task(&data.tasks, filter, "Which task would you like to move?") {
+                    Some(index) => {
+                        let selected = FuzzySelect::new().with_prompt("Move it to").items(&others).interact().unwrap();
+                        lists::move_task(data, index, others[selected]).map(|()| true)
+                    },
+                    None => Ok(false),
+                }
+            }
+        },
+        6_usize.. => panic!("Invalid operator supplied"),
     };
-    tasks.push(new_task);
+
+    match result {
+        Ok(modified) => modified,
+        Err(message) => {
+            println!("{}", style(message).red());
+            false
+        }
+    }
+}
+
+fn import_tasks(data: &mut TodoData) -> bool {
+    let path: String = Input::new().with_prompt("File to import").interact_text().unwrap();
+    let path = PathBuf::from(path.trim());
+    let format = prompt_format(Format::from_path(&path).unwrap_or(Format::Markdown));
+
+    let imported = fs::read_to_string(&path).map_err(|err| format!("Could not read {}: {}", path.display(), err)).and_then(|contents| formats::import(&contents, format));
+    match imported {
+        Ok(imported) => {
+            let count = formats::add_imported(data, imported);
+            println!("{}", style(format!("Imported {} tasks", count)).blue());
+            count > 0
+        }
+        Err(message) => {
+            println!("{}", style(message).red());
+            false
+        }
+    }
+}
+
+fn export_tasks(tasks: &[Task]) {
+    let path: String = Input::new().with_prompt("File to export to").interact_text().unwrap();
+    let path = PathBuf::from(path.trim());
+    let format = prompt_format(Format::from_path(&path).unwrap_or(Format::Markdown));
+
+    match fs::write(&path, formats::export(tasks, format)) {
+        Ok(()) => println!("{}", style(format!("Exported {} tasks to {}", tasks.len(), path.display())).blue()),
+        Err(err) => println!("{}", style(format!("Could not write {}: {}", path.display(), err)).red()),
+    }
+}
+
+fn export_timesheet(data: &TodoData) {
+    let path: String = Input::new().with_prompt("File to write the timesheet to").interact_text().unwrap();
+    let path = PathBuf::from(path.trim());
+    let selected = FuzzySelect::new().with_prompt("Total time").items(&Timesheet::ALL).default(0).interact().unwrap();
+
+    match fs::write(&path, timer::export_csv(&stats::counted_tasks(data), &data.current_list, Timesheet::ALL[selected])) {
+        Ok(()) => println!("{}", style(format!("Wrote the timesheet to {}", path.display())).blue()),
+        Err(err) => println!("{}", style(format!("Could not write {}: {}", path.display(), err)).red()),
+    }
+}
+
+fn prompt_format(default: Format) -> Format {
+    let default = Format::ALL.iter().position(|format| *format == default).unwrap();
+    let selected = FuzzySelect::new().with_prompt("Format").items(&Format::ALL).default(default).interact().unwrap();
+    Format::ALL[selected]
+}
+
+//Only tasks matching the filter are offered, and the index returned is the task's position in the full list
+fn pick_task(tasks: &[Task], filter: &[String], prompt: &str) -> Option<usize> {
+    if tasks.is_empty() {
+        println!("{}", style("You have no tasks").blue());
+        return None;
+    }
+
+    let matching: Vec<usize> = (0..tasks.len()).filter(|&index| tags::matches_filter(&tasks[index], filter)).collect();
+    if matching.is_empty() {
+        println!("{}", style("No tasks match the filter").blue());
+        return None;
+    }
+
+    let items: Vec<&Task> = matching.iter().map(|&index| &tasks[index]).collect();
+    let selected = FuzzySelect::new().with_prompt(prompt).items(&items).interact().unwrap();
+    Some(matching[selected])
 }
 
-fn complete_task(tasks: &mut Vec<Task>) {
-    let selected_task = FuzzySelect::new().with_prompt("Which task would you like to complete?").items(&tasks).interact().unwrap();
-    tasks[selected_task].completed = true;
+//An empty answer means the task has no due date
+fn prompt_due(current: Option<Due>) -> Option<Due> {
+    let initial = current.map(|due| due.to_string()).unwrap_or_default();
+    let input: String = Input::new()
+        .with_prompt("Due date (e.g. 2024-05-01, tomorrow 09:00, in 3 days, empty for none)")
+        .with_initial_text(initial)
+        .allow_empty(true)
+        .validate_with(|input: &String| -> Result<(), String> {
+            if input.trim().is_empty() {
+                return Ok(());
+            }
+            due::parse_due(input, due::now().date()).map(|_| ())
+        })
+        .interact_text()
+        .unwrap();
+
+    if input.trim().is_empty() {
+        return None;
+    }
+    due::parse_due(&input, due::now().date()).ok()
+}
+
+fn prompt_move() -> Move {
+    const MOVES: [Move; 4] = [Move::Up, Move::Down, Move::Top, Move::Bottom];
+    let mut items: Vec<String> = MOVES.iter().map(|to| to.to_string()).collect();
+    items.push(String::from("to a position"));
+
+    let selected = FuzzySelect::new().with_prompt("Move it").items(&items).default(0).interact().unwrap();
+    if selected < MOVES.len() {
+        return MOVES[selected];
+    }
+    let position: usize = Input::new()
+        .with_prompt("Position among the tasks at the same level (1 is the top)")
+        .validate_with(|position: &usize| if *position > 0 { Ok(()) } else { Err("Positions start at 1") })
+        .interact_text()
+        .unwrap();
+    Move::To(position)
 }
 
-fn edit_task(tasks: &mut Vec<Task>) {
-    let selected_task = FuzzySelect::new().with_prompt("Which task would you like to edit?").items(&tasks).interact().unwrap();
-    let new_task = Input::new().with_prompt("Enter new task name").interact_text().unwrap();
-    tasks[selected_task].name = new_task;
+fn prompt_search_mode() -> SearchMode {
+    let selected = FuzzySelect::new().with_prompt("Match by").items(&SearchMode::ALL).default(0).interact().unwrap();
+    SearchMode::ALL[selected]
 }
 
-fn delete_task(tasks: &mut Vec<Task>) {
-    let selected_task = FuzzySelect::new().with_prompt("Which task would you like to delete?").items(&tasks).interact().unwrap();
-    tasks.remove(selected_task);
+fn prompt_priority(current: Priority) -> Priority {
+    let current = Priority::ALL.iter().position(|priority| *priority == current).unwrap();
+    let selected = FuzzySelect::new().with_prompt("Priority").items(&Priority::ALL).default(current).interact().unwrap();
+    Priority::ALL[selected]
 }
 
-fn remove_completed_tasks(tasks: &mut Vec<Task>) {
-    tasks.retain(|task| task.completed == false);
-    println!("{}", style("Removed all completed tasks").blue());
+fn prompt_tags(current: &[String]) -> Vec<String> {
+    let input: String = Input::new().with_prompt("Tags (e.g. +work @phone, empty for none)").with_initial_text(current.join(" ")).allow_empty(true).interact_text().unwrap();
+    tags::parse_tag_list(&input)
 }
 
-fn clear_tasks(tasks: &mut Vec<Task>) {
-    tasks.clear();
-    println!("{}", style("All tasks removed").blue());
+//An empty answer means the task does not repeat
+fn prompt_repeat(current: Option<&Recurrence>) -> Option<Recurrence> {
+    let initial = current.map(|repeat| repeat.to_string()).unwrap_or_default();
+    let input: String = Input::new()
+        .with_prompt("Repeat (e.g. daily, weekly on mon thu, monthly, every 3 days, empty for never)")
+        .with_initial_text(initial)
+        .allow_empty(true)
+        .validate_with(|input: &String| -> Result<(), String> {
+            if input.trim().is_empty() {
+                return Ok(());
+            }
+            due::parse_recurrence(input).map(|_| ())
+        })
+        .interact_text()
+        .unwrap();
+
+    if input.trim().is_empty() {
+        return None;
+    }
+    due::parse_recurrence(&input).ok()
 }
 
 fn help() {
     println!("{}", style("A simple CLI tool for managing a To-Do list").blue());  
     println!();
-    println!("{}", style("Show: Show all tasks. Incomplete tasks are shown in red while complete tasks are shown in green and struck through.").blue());
+    println!("{}", style("Show: Show all tasks in the chosen sort order. Overdue tasks are shown in magenta, tasks due today in yellow, upcoming tasks in cyan and other incomplete tasks in red. Complete tasks are shown in green and struck through.").blue());
+    println!();
+    println!("{}", style("Tui: Open a full-screen view of the list with keyboard shortcuts to add, complete, edit, delete and clean tasks. Press q to return.").blue());
+    println!();
+    println!("{}", style("Sort: Choose whether tasks are shown by due date, priority, creation, name or in the manual order set with move. The choice is remembered.").blue());
+    println!();
+    println!("{}", style("Due: Show incomplete tasks due within a number of days, including overdue tasks.").blue());
+    println!();
+    println!("{}", style("Filter: Only show and pick tasks with the given tags, e.g. +work @phone. Leave empty to show all tasks again.").blue());
+    println!();
+    println!("{}", style("Search: Find tasks, including completed ones, whose names contain some text, match a regular expression or fuzzily match what you type. Matches are shown with whether they are done and their position in the list, and one can then be completed, edited or deleted.").blue());
+    println!();
+    println!("{}", style("Tags: Show the number of open tasks for each tag.").blue());
+    println!();
+    println!("{}", style("Stats: Show how many tasks were created and completed each day or week with sparklines, the average time taken to complete a task, the current and longest streak of days with a task completed, and the open tasks by priority and tag. Archived tasks are included.").blue());
+    println!();
+    println!("{}", style("Lists: Show, create, switch between, rename or delete named lists, or move a task and its subtasks to another list. The current list is shown in the menu prompt.").blue());
+    println!();
+    println!("{}", style("Add: Add a new task to the list, optionally with a due date such as 2024-05-01, tomorrow 09:00 or in 3 days, a priority, tags and how often it repeats. Words in the name starting with + or @ also become tags.").blue());
+    println!();
+    println!("{}", style("Subtask: Add a task underneath another task. Subtasks are shown indented below their parent along with how many are done.").blue());
+    println!();
+    println!("{}", style("Complete: Mark a task as completed, optionally along with its subtasks. A task is completed automatically once all of its subtasks are. Completing a repeating task adds its next occurrence with the next due date.").blue());
+    println!();
+    println!("{}", style("Edit: Change the name, due date, priority, tags or repeat of a task.").blue());
+    println!();
+    println!("{}", style("Move: Move a task up, down, to the top or bottom, or to a position among the tasks at the same level. The list is then shown in manual order, which is remembered like the other sort orders.").blue());
+    println!();
+    println!("{}", style("View: Show everything about a task, including its notes. Tasks with notes are marked [notes] in the list.").blue());
+    println!();
+    println!("{}", style("Notes: Write or change a task's notes in your $EDITOR. Notes can be as many lines as you like, and saving them empty removes them.").blue());
+    println!();
+    println!("{}", style("Start: Start timing a task, stopping the timer of any other task. The task being timed and its running time are shown in the menu prompt, and each task's tracked time is shown in the list.").blue());
+    println!();
+    println!("{}", style("Stop: Stop the running timer.").blue());
+    println!();
+    println!("{}", style("Delete: Remove a task and its subtasks from the list.").blue());
+    println!();
+    println!("{}", style("Bulk: Complete, delete, add or remove tags on, or set the priority of several tasks at once. Type a pattern to select every task whose name contains it, then adjust the selection with space.").blue());
+    println!();
+    println!("{}", style("Clean: Move all completed tasks from the list to the archive.").blue());
+    println!();
+    println!("{}", style("Archive: Browse or search the tasks moved there by clean, restore one to the current list, or permanently purge those completed more than a number of days ago.").blue());
+    println!();
+    println!("{}", style("Empty: Remove all tasks from the list, after asking for confirmation.").blue());
     println!();
-    println!("{}", style("Add: Add a new task to the list.").blue());
+    println!("{}", style("Undo: Undo the last change to the list. Changes are remembered between runs.").blue());
     println!();
-    println!("{}", style("Complete: Mark a task as completed.").blue());
+    println!("{}", style("Redo: Redo the last change that was undone.").blue());
     println!();
-    println!("{}", style("Edit: Change the name of a task.").blue());
+    println!("{}", style("Import: Add the tasks from a todo.txt, JSON or Markdown checklist file to the list.").blue());
     println!();
-    println!("{}", style("Delete: Remove a task from the list.").blue());
+    println!("{}", style("Export: Write the list to a todo.txt, JSON or Markdown checklist file.").blue());
     println!();
-    println!("{}", style("Clean: Remove all completed tasks from the list.").blue());
+    println!("{}", style("Timesheet: Write the time tracked against the list's tasks to a CSV file, totalled per task or per task and day.").blue());
     println!();
-    println!("{}", style("Empty: Remove all tasks from the list.").blue());
+    println!("{}", style("Every operation can also be run directly from the command line, e.g. `todo_list add \"name\"`. Run `todo_list --help` for details.").blue());
 }
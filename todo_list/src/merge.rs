@@ -0,0 +1,279 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use crate::storage::TodoData;
+use crate::tasks::{self, Task};
+
+//Folds the changes someone else saved (theirs) into ours, given what the file held when we loaded it (base).
+//Tasks are matched by id and merged field by field, so two people editing different tasks, or different
+//parts of the same task, both keep their changes. When both changed the same field, ours wins.
+//Returns the ids of tasks where that happened
+pub fn merge(ours: &mut TodoData, base: TodoData, theirs: TodoData) -> Vec<u32> {
+    renumber_new_tasks(ours, &base, &theirs);
+    ours.last_id = ours.last_id.max(theirs.last_id);
+
+    let mut conflicts = Vec::new();
+    let base_lists = all_lists(&base);
+    let their_lists = all_lists(&theirs);
+    let our_lists = all_lists(ours);
+
+    let names: BTreeSet<&String> = base_lists.keys().chain(our_lists.keys()).chain(their_lists.keys()).collect();
+    let mut merged_lists: BTreeMap<String, Vec<Task>> = BTreeMap::new();
+    for name in names {
+        let (base_list, our_list, their_list) = (base_lists.get(name), our_lists.get(name), their_lists.get(name));
+        //A list someone created or deleted stays that way, unless it is the one we are using
+        let kept = if our_list.is_some() == base_list.is_some() { their_list.is_some() } else { our_list.is_some() };
+        if !kept && *name != ours.current_list {
+            continue;
+        }
+        let our_list = our_list.map(|tasks| tasks.as_slice()).unwrap_or_default();
+        let mut merged = merge_items(
+            base_list.map(|tasks| tasks.as_slice()).unwrap_or_default(),
+            our_list,
+            their_list.map(|tasks| tasks.as_slice()).unwrap_or_default(),
+            |task| task.id,
+            &mut conflicts,
+        );
+        break_parent_loops(&mut merged, our_list);
+        merged_lists.insert(name.clone(), merged);
+    }
+
+    ours.archive = merge_items(&base.archive, &ours.archive, &theirs.archive, |entry| entry.task.id, &mut conflicts);
+    ours.tasks = merged_lists.remove(&ours.current_list).unwrap_or_default();
+    ours.lists = merged_lists;
+
+    conflicts.sort();
+    conflicts.dedup();
+    conflicts
+}
+
+fn all_lists(data: &TodoData) -> BTreeMap<String, Vec<Task>> {
+    let mut lists = data.lists.clone();
+    lists.insert(data.current_list.clone(), data.tasks.clone());
+    lists
+}
+
+fn all_ids(data: &TodoData) -> HashSet<u32> {
+    let archived = data.archive.iter().map(|entry| &entry.task);
+    data.tasks.iter().chain(data.lists.values().flatten()).chain(archived).map(|task| task.id).collect()
+}
+
+//Tasks we added since loading may have been given the same ids as tasks someone else added, so ours move to new ids
+fn renumber_new_tasks(ours: &mut TodoData, base: &TodoData, theirs: &TodoData) {
+    let base_ids = all_ids(base);
+    let their_ids = all_ids(theirs);
+    let clashes: Vec<u32> = all_ids(ours).into_iter().filter(|id| !base_ids.contains(id) && their_ids.contains(id)).collect();
+
+    ours.last_id = ours.last_id.max(theirs.last_id);
+    for old_id in clashes {
+        let new_id = ours.next_id();
+        let archived = ours.archive.iter_mut().map(|entry| &mut entry.task);
+        for task in ours.tasks.iter_mut().chain(ours.lists.values_mut().flatten()).chain(archived) {
+            if task.id == old_id {
+                task.id = new_id;
+            }
+            if task.parent == Some(old_id) {
+                task.parent = Some(new_id);
+            }
+        }
+    }
+}
+
+//Moving two tasks under each other at the same time leaves them in a loop. Their moves are undone until none is left
+fn break_parent_loops(tasks: &mut [Task], ours: &[Task]) {
+    let our_parent = |task: &Task| ours.iter().find(|our_task| our_task.id == task.id).and_then(|our_task| our_task.parent);
+    loop {
+        let looped: Vec<usize> = (0..tasks.len()).filter(|&index| tasks::in_parent_loop(tasks, index)).collect();
+        if looped.is_empty() {
+            return;
+        }
+        match looped.iter().find(|&&index| tasks[index].parent != our_parent(&tasks[index])) {
+            Some(&index) => tasks[index].parent = our_parent(&tasks[index]),
+            None => tasks[looped[0]].parent = None,
+        }
+    }
+}
+
+//Their order is kept, with anything only we added at the end. If only we moved tasks around, our order is kept
+//instead, with anything only they added at the end
+fn merge_items<T>(base: &[T], ours: &[T], theirs: &[T], id: impl Fn(&T) -> u32, conflicts: &mut Vec<u32>) -> Vec<T>
+where
+    T: Serialize + DeserializeOwned + Clone + PartialEq,
+{
+    let find = |items: &[T], wanted: u32| items.iter().find(|item| id(item) == wanted).cloned();
+    let mut merged = Vec::new();
+
+    for their_item in theirs {
+        match (find(base, id(their_item)), find(ours, id(their_item))) {
+            (Some(base_item), Some(our_item)) => merged.push(merge_fields(&base_item, &our_item, their_item, id(their_item), conflicts)),
+            //We deleted it, but it is kept if they changed it meanwhile
+            (Some(base_item), None) if base_item != *their_item => merged.push(their_item.clone()),
+            (Some(_), None) => {}
+            (None, _) => merged.push(their_item.clone()),
+        }
+    }
+
+    for our_item in ours.iter().filter(|item| find(theirs, id(item)).is_none()) {
+        match find(base, id(our_item)) {
+            //They deleted it, but it is kept if we changed it meanwhile
+            Some(base_item) if base_item != *our_item => merged.push(our_item.clone()),
+            Some(_) => {}
+            None => merged.push(our_item.clone()),
+        }
+    }
+
+    let in_all = |wanted: u32| [base, ours, theirs].iter().all(|items| items.iter().any(|item| id(item) == wanted));
+    let order = |items: &[T]| -> Vec<u32> { items.iter().map(&id).filter(|&item_id| in_all(item_id)).collect() };
+    let base_order = order(base);
+    if order(ours) != base_order && order(theirs) == base_order {
+        merged.sort_by_key(|item| ours.iter().position(|our_item| id(our_item) == id(item)).unwrap_or(usize::MAX));
+    }
+    merged
+}
+
+fn merge_fields<T>(base: &T, ours: &T, theirs: &T, id: u32, conflicts: &mut Vec<u32>) -> T
+where
+    T: Serialize + DeserializeOwned + Clone + PartialEq,
+{
+    if ours == base {
+        return theirs.clone();
+    }
+    if theirs == base || ours == theirs {
+        return ours.clone();
+    }
+
+    let (base_fields, our_fields, their_fields) = match (serde_json::to_value(base), serde_json::to_value(ours), serde_json::to_value(theirs)) {
+        (Ok(serde_json::Value::Object(base)), Ok(serde_json::Value::Object(ours)), Ok(serde_json::Value::Object(theirs))) => (base, ours, theirs),
+        _ => return ours.clone(),
+    };
+
+    let mut merged = their_fields.clone();
+    for (key, our_value) in our_fields {
+        if base_fields.get(&key) == Some(&our_value) {
+            continue;
+        }
+        if their_fields.get(&key) != base_fields.get(&key) && their_fields.get(&key) != Some(&our_value) {
+            conflicts.push(id);
+        }
+        merged.insert(key, our_value);
+    }
+    serde_json::from_value(serde_json::Value::Object(merged)).unwrap_or_else(|_| ours.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tasks::Priority;
+
+    fn base_tasks() -> Vec<Task> {
+        vec![Task::new(1, String::from("Write report")), Task::new(2, String::from("Book flights")), Task::new(3, String::from("Pay rent"))]
+    }
+
+    fn data(tasks: Vec<Task>) -> TodoData {
+        TodoData {
+            last_id: tasks.iter().map(|task| task.id).max().unwrap_or(0),
+            tasks,
+            ..TodoData::default()
+        }
+    }
+
+    fn names(data: &TodoData) -> Vec<(u32, &str)> {
+        data.tasks.iter().map(|task| (task.id, task.name.as_str())).collect()
+    }
+
+    #[test]
+    fn edits_to_different_tasks_are_both_kept() {
+        let base = base_tasks();
+        let mut ours = base.clone();
+        ours[0].name = String::from("Write final report");
+        let mut theirs = base.clone();
+        theirs[1].completed = true;
+        theirs[0].priority = Priority::High;
+
+        let mut merged = data(ours);
+        let conflicts = merge(&mut merged, data(base), data(theirs));
+
+        assert!(conflicts.is_empty());
+        assert_eq!(merged.tasks[0].name, "Write final report");
+        assert_eq!(merged.tasks[0].priority, Priority::High);
+        assert!(merged.tasks[1].completed);
+    }
+
+    #[test]
+    fn ours_wins_when_both_change_the_same_field() {
+        let base = base_tasks();
+        let mut ours = base.clone();
+        ours[2].name = String::from("Pay rent early");
+        let mut theirs = base.clone();
+        theirs[2].name = String::from("Pay rent late");
+
+        let mut merged = data(ours);
+        let conflicts = merge(&mut merged, data(base), data(theirs));
+
+        assert_eq!(conflicts, vec![3]);
+        assert_eq!(merged.tasks[2].name, "Pay rent early");
+    }
+
+    #[test]
+    fn deleted_tasks_are_kept_if_the_other_side_edited_them() {
+        let base = base_tasks();
+        let mut ours = base.clone();
+        ours.retain(|task| task.id == 3);
+        let mut theirs = base.clone();
+        theirs[0].completed = true;
+
+        let mut merged = data(ours);
+        merge(&mut merged, data(base), data(theirs));
+
+        assert_eq!(names(&merged), vec![(1, "Write report"), (3, "Pay rent")]);
+        assert!(merged.tasks[0].completed);
+    }
+
+    #[test]
+    fn new_tasks_with_the_same_id_are_both_kept() {
+        let base = base_tasks();
+        let mut ours = base.clone();
+        ours.push(Task::new(4, String::from("Call the bank")));
+        let mut theirs = base.clone();
+        theirs.push(Task::new(4, String::from("Water plants")));
+
+        let mut merged = data(ours);
+        merge(&mut merged, data(base), data(theirs));
+
+        assert_eq!(&names(&merged)[3..], &[(4, "Water plants"), (5, "Call the bank")]);
+        assert_eq!(merged.last_id, 5);
+    }
+
+    #[test]
+    fn moving_tasks_under_each_other_does_not_loop() {
+        let base = base_tasks();
+        let mut ours = base.clone();
+        ours[0].parent = Some(2);
+        let mut theirs = base.clone();
+        theirs[1].parent = Some(1);
+        theirs[2].parent = Some(1);
+
+        let mut merged = data(ours);
+        merge(&mut merged, data(base), data(theirs));
+
+        let parents: Vec<Option<u32>> = merged.tasks.iter().map(|task| task.parent).collect();
+        assert_eq!(parents, vec![Some(2), None, Some(1)]);
+    }
+
+    #[test]
+    fn our_order_is_kept_when_only_we_moved_tasks() {
+        let base = base_tasks();
+        let mut ours = base.clone();
+        ours.swap(0, 2);
+        let mut theirs = base.clone();
+        theirs[1].completed = true;
+        theirs.push(Task::new(4, String::from("Water plants")));
+
+        let mut merged = data(ours);
+        merge(&mut merged, data(base), data(theirs));
+
+        assert_eq!(names(&merged), vec![(3, "Pay rent"), (2, "Book flights"), (1, "Write report"), (4, "Water plants")]);
+        assert!(merged.tasks[1].completed);
+    }
+}
@@ -0,0 +1,126 @@
+use clap::ValueEnum;
+use console::style;
+use console::Style;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use regex::RegexBuilder;
+use std::cmp::Reverse;
+use std::fmt;
+
+use crate::due;
+use crate::tasks;
+use crate::tasks::{SortOrder, Task};
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Default)]
+pub enum SearchMode {
+    #[default]
+    Substring,
+    Regex,
+    Fuzzy,
+}
+
+impl SearchMode {
+    pub const ALL: [SearchMode; 3] = [SearchMode::Substring, SearchMode::Regex, SearchMode::Fuzzy];
+}
+
+impl fmt::Display for SearchMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            SearchMode::Substring => write!(f, "substring"),
+            SearchMode::Regex => write!(f, "regular expression"),
+            SearchMode::Fuzzy => write!(f, "fuzzy"),
+        }
+    }
+}
+
+pub struct SearchMatch {
+    pub index: usize,
+    //The positions of the characters in the name that matched, for highlighting
+    pub highlight: Vec<usize>,
+    score: i64,
+}
+
+//Substring searches ignore case. Fuzzy matches come back best first, the others in list order
+pub fn search(tasks: &[Task], query: &str, mode: SearchMode) -> Result<Vec<SearchMatch>, String> {
+    if query.is_empty() {
+        return Err(String::from("Enter something to search for"));
+    }
+
+    let mut matches: Vec<SearchMatch> = match mode {
+        SearchMode::Substring | SearchMode::Regex => {
+            let pattern = if mode == SearchMode::Substring { regex::escape(query) } else { query.to_string() };
+            let regex = RegexBuilder::new(&pattern)
+                .case_insensitive(mode == SearchMode::Substring)
+                .build()
+                .map_err(|err| format!("Invalid regular expression: {}", err))?;
+
+            tasks
+                .iter()
+                .enumerate()
+                .filter_map(|(index, task)| {
+                    let ranges: Vec<_> = regex.find_iter(&task.name).map(|found| found.range()).collect();
+                    if ranges.is_empty() {
+                        return None;
+                    }
+                    let highlight = task
+                        .name
+                        .char_indices()
+                        .enumerate()
+                        .filter(|(_, (byte, _))| ranges.iter().any(|range| range.contains(byte)))
+                        .map(|(position, _)| position)
+                        .collect();
+                    Some(SearchMatch { index, highlight, score: 0 })
+                })
+                .collect()
+        }
+        SearchMode::Fuzzy => {
+            let matcher = SkimMatcherV2::default();
+            tasks
+                .iter()
+                .enumerate()
+                .filter_map(|(index, task)| {
+                    let (score, highlight) = matcher.fuzzy_indices(&task.name, query)?;
+                    Some(SearchMatch { index, highlight, score })
+                })
+                .collect()
+        }
+    };
+
+    matches.sort_by_key(|found| Reverse(found.score));
+    Ok(matches)
+}
+
+//Matched characters are drawn bold and underlined on top of the name's usual style
+pub fn highlight(name: &str, positions: &[usize], name_style: &Style) -> String {
+    name.chars()
+        .enumerate()
+        .map(|(position, c)| {
+            if positions.contains(&position) {
+                name_style.clone().bold().underlined().apply_to(c).to_string()
+            } else {
+                name_style.apply_to(c).to_string()
+            }
+        })
+        .collect()
+}
+
+//Each match is shown with its id, whether it is done, and where it appears when the list is shown
+pub fn show_matches(tasks: &[Task], matches: &[SearchMatch], sort: SortOrder, query: &str) {
+    if matches.is_empty() {
+        println!("{}", style(format!("No tasks match \"{}\"", query)).blue());
+        return;
+    }
+
+    let order: Vec<u32> = tasks::tree_order(tasks, sort, &[]).iter().map(|(task, _)| task.id).collect();
+    let now = due::now();
+    for found in matches {
+        let task = &tasks[found.index];
+        let (due_status, _) = tasks::due_label(task, now);
+        let name_style = tasks::status_style(task, due_status.as_ref());
+        let status = if task.completed { style("[done]").green() } else { style("[open]").yellow() };
+
+        let name = highlight(&task.name, &found.highlight, &name_style);
+        let position = order.iter().position(|id| *id == task.id).unwrap() + 1;
+        println!("{:>3}. {} {} {}", task.id, status, name, style(format!("(position {} of {})", position, order.len())).dim());
+    }
+}
@@ -0,0 +1,206 @@
+use chrono::{Datelike, Days, Duration, NaiveDate};
+use clap::ValueEnum;
+use console::style;
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::due;
+use crate::storage::TodoData;
+use crate::tags;
+use crate::tasks::{Priority, Task};
+
+const SPARK_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Default)]
+pub enum Period {
+    #[default]
+    Day,
+    Week,
+}
+
+impl Period {
+    pub const ALL: [Period; 2] = [Period::Day, Period::Week];
+
+    //Weeks start on Monday
+    fn start(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Day => date,
+            Period::Week => date - Days::new(date.weekday().num_days_from_monday() as u64),
+        }
+    }
+
+    //None before the earliest date there is
+    fn previous(&self, start: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            Period::Day => start.checked_sub_days(Days::new(1)),
+            Period::Week => start.checked_sub_days(Days::new(7)),
+        }
+    }
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Period::Day => write!(f, "day"),
+            Period::Week => write!(f, "week"),
+        }
+    }
+}
+
+//The current list's tasks along with those archived from it, so cleaning does not lose any history
+pub fn counted_tasks(data: &TodoData) -> Vec<&Task> {
+    let archived = data.archive.iter().filter(|entry| entry.list == data.current_list).map(|entry| &entry.task);
+    data.tasks.iter().chain(archived).collect()
+}
+
+//How many periods to show when not told
+pub fn default_periods(period: Period) -> usize {
+    match period {
+        Period::Day => 14,
+        Period::Week => 8,
+    }
+}
+
+pub fn show_stats(tasks: &[&Task], open: &[Task], period: Period, periods: usize) {
+    let today = due::now().date();
+
+    println!("{}", style(format!("Created and completed per {} (last {} {}s)", period, periods, period)).blue().bold());
+    let rows = throughput(tasks, period, periods, today);
+    let heading = if period == Period::Week { "week of" } else { "day" };
+    println!("{:<12} {:>8} {:>10}", style(heading).dim(), style("created").dim(), style("completed").dim());
+    for (start, created, completed) in &rows {
+        println!("{:<12} {:>8} {:>10}", start.format("%Y-%m-%d").to_string(), created, completed);
+    }
+    let created: Vec<usize> = rows.iter().map(|(_, created, _)| *created).collect();
+    let completed: Vec<usize> = rows.iter().map(|(_, _, completed)| *completed).collect();
+    println!("{:<12} {}", "created", style(sparkline(&created)).cyan());
+    println!("{:<12} {}", "completed", style(sparkline(&completed)).green());
+    println!();
+
+    match average_completion(tasks) {
+        Some((average, count)) => println!("Average time to complete: {} (over {} tasks)", format_duration(average), count),
+        None => println!("Average time to complete: no completed tasks yet"),
+    }
+    let (current, longest) = streaks(tasks, today);
+    println!("Streak: {} (longest {})", plural_days(current), plural_days(longest));
+    println!();
+
+    println!("{}", style("Open tasks by priority").blue().bold());
+    for priority in Priority::ALL.iter().rev() {
+        let count = open.iter().filter(|task| !task.completed && task.priority == *priority).count();
+        println!("{:<8}  {}", priority.to_string(), count);
+    }
+    println!();
+
+    println!("{}", style("Open tasks by tag").blue().bold());
+    tags::show_tag_summary(open);
+}
+
+//The number of tasks created and completed in each of the last few periods, oldest first
+fn throughput(tasks: &[&Task], period: Period, periods: usize, today: NaiveDate) -> Vec<(NaiveDate, usize, usize)> {
+    let mut starts = vec![period.start(today)];
+    while starts.len() < periods {
+        match period.previous(*starts.last().unwrap()) {
+            Some(start) => starts.push(start),
+            None => break,
+        }
+    }
+    starts.reverse();
+
+    starts
+        .into_iter()
+        .map(|start| {
+            let created = tasks.iter().filter(|task| period.start(task.created.date_naive()) == start).count();
+            let completed = tasks
+                .iter()
+                .filter(|task| task.completed && task.completed_at.is_some_and(|at| period.start(at.date_naive()) == start))
+                .count();
+            (start, created, completed)
+        })
+        .collect()
+}
+
+//Scaled so the busiest period gets a full bar
+fn sparkline(values: &[usize]) -> String {
+    let highest = values.iter().copied().max().unwrap_or(0);
+    values
+        .iter()
+        .map(|&value| SPARK_LEVELS[(value * (SPARK_LEVELS.len() - 1)).checked_div(highest).unwrap_or(0)])
+        .collect()
+}
+
+fn average_completion(tasks: &[&Task]) -> Option<(Duration, usize)> {
+    let durations: Vec<Duration> = tasks
+        .iter()
+        .filter(|task| task.completed)
+        .filter_map(|task| task.completed_at.map(|at| at - task.created))
+        .collect();
+    if durations.is_empty() {
+        return None;
+    }
+
+    let total: Duration = durations.iter().sum();
+    Some((total / durations.len() as i32, durations.len()))
+}
+
+//Such as "3d 4h" or "2h 5m", for the time tasks took to complete and the time tracked on them
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.num_minutes().max(0);
+    let (days, hours, minutes) = (minutes / (24 * 60), minutes / 60 % 24, minutes % 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+//A streak is a run of days with at least one task completed. The current one is still going
+//if nothing has been completed yet today
+fn streaks(tasks: &[&Task], today: NaiveDate) -> (usize, usize) {
+    let days: BTreeSet<NaiveDate> = tasks
+        .iter()
+        .filter(|task| task.completed)
+        .filter_map(|task| task.completed_at.map(|at| at.date_naive()))
+        .collect();
+
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for &day in &days {
+        run = if previous.is_some_and(|previous| previous.succ_opt() == Some(day)) { run + 1 } else { 1 };
+        longest = longest.max(run);
+        previous = Some(day);
+    }
+
+    let mut day = if days.contains(&today) { today } else { today - Days::new(1) };
+    let mut current = 0;
+    while days.contains(&day) {
+        current += 1;
+        day = day - Days::new(1);
+    }
+    (current, longest)
+}
+
+fn plural_days(days: usize) -> String {
+    if days == 1 {
+        String::from("1 day")
+    } else {
+        format!("{} days", days)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn periods_stop_at_the_earliest_date() {
+        let today = NaiveDate::MIN.checked_add_days(Days::new(2)).unwrap();
+        let rows = throughput(&[], Period::Day, 100_000, today);
+
+        let starts: Vec<NaiveDate> = rows.iter().map(|(start, _, _)| *start).collect();
+        assert_eq!(starts, vec![NaiveDate::MIN, NaiveDate::MIN.succ_opt().unwrap(), today]);
+    }
+}
@@ -0,0 +1,202 @@
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::archive::ArchivedTask;
+use crate::history::History;
+use crate::lists;
+use crate::merge;
+use crate::tasks::{SortOrder, Task};
+
+const DATA_DIR: &str = "todo_list";
+const DATA_FILE: &str = "tasks.json";
+
+#[derive(Serialize, Deserialize)]
+pub struct TodoData {
+    //The tasks in the current list. Switching lists swaps them with one of the other lists
+    pub tasks: Vec<Task>,
+    #[serde(default = "lists::default_list_name")]
+    pub current_list: String,
+    //Every other list, by name
+    #[serde(default)]
+    pub lists: BTreeMap<String, Vec<Task>>,
+    //Completed tasks removed by clean, from every list
+    #[serde(default)]
+    pub archive: Vec<ArchivedTask>,
+    //The last id handed out, kept so ids of deleted tasks are never reused
+    #[serde(default)]
+    pub last_id: u32,
+    //The order `show` lists tasks in, remembered between runs
+    #[serde(default)]
+    pub sort: SortOrder,
+    #[serde(default)]
+    pub history: History,
+}
+
+impl Default for TodoData {
+    fn default() -> TodoData {
+        TodoData {
+            tasks: Vec::new(),
+            current_list: lists::default_list_name(),
+            lists: BTreeMap::new(),
+            archive: Vec::new(),
+            last_id: 0,
+            sort: SortOrder::default(),
+            history: History::default(),
+        }
+    }
+}
+
+impl TodoData {
+    pub fn next_id(&mut self) -> u32 {
+        self.last_id += 1;
+        self.last_id
+    }
+
+    fn assign_missing_ids(&mut self) {
+        let archived = self.archive.iter().map(|entry| &entry.task);
+        let highest_id = self.tasks.iter().chain(self.lists.values().flatten()).chain(archived).map(|task| task.id).max().unwrap_or(0);
+        self.last_id = self.last_id.max(highest_id);
+
+        for index in 0..self.tasks.len() {
+            if self.tasks[index].id == 0 {
+                self.tasks[index].id = self.next_id();
+            }
+        }
+    }
+}
+
+pub fn default_path() -> PathBuf {
+    let data_dir = dirs::data_dir().unwrap_or_else(|| PathBuf::from("."));
+    data_dir.join(DATA_DIR).join(DATA_FILE)
+}
+
+pub fn load(path: &Path) -> io::Result<TodoData> {
+    parse(&read_contents(path)?)
+}
+
+//A missing file reads as empty
+fn read_contents(path: &Path) -> io::Result<String> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(contents),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        Err(err) => Err(err),
+    }
+}
+
+fn parse(contents: &str) -> io::Result<TodoData> {
+    if contents.is_empty() {
+        return Ok(TodoData::default());
+    }
+
+    let mut data: TodoData = serde_json::from_str(contents).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    data.assign_missing_ids();
+    Ok(data)
+}
+
+//Write to a temporary file next to the real one and rename it into place, so a crash
+//part way through a save leaves the previous list intact
+pub fn save(path: &Path, data: &TodoData) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let contents = serde_json::to_string_pretty(data)?;
+    let temp_path = path.with_extension("json.tmp");
+
+    let mut file = fs::File::create(&temp_path)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+
+    fs::rename(&temp_path, path)
+}
+
+//Where the tasks are loaded from and saved to. A shared file can be changed by other people while
+//we have it open, so it is locked while being read or written and their changes are merged into ours
+pub struct Store {
+    path: PathBuf,
+    //For a shared file, its contents when we last loaded or saved it
+    shared: Option<String>,
+}
+
+impl Store {
+    pub fn open(path: PathBuf, shared: bool) -> io::Result<(Store, TodoData)> {
+        if !shared {
+            let data = load(&path)?;
+            return Ok((Store { path, shared: None }, data));
+        }
+
+        let lock = lock(&path)?;
+        let contents = read_contents(&path)?;
+        let data = parse(&contents)?;
+        lock.unlock()?;
+        Ok((Store { path, shared: Some(contents) }, data))
+    }
+
+    //Returns a message when changes someone else saved had to be merged in first
+    pub fn save(&mut self, data: &mut TodoData) -> io::Result<Option<String>> {
+        if self.shared.is_none() {
+            save(&self.path, data)?;
+            return Ok(None);
+        }
+
+        let lock = lock(&self.path)?;
+        let message = self.merge_changes(data)?;
+        save(&self.path, data)?;
+        self.shared = Some(read_contents(&self.path)?);
+        lock.unlock()?;
+        Ok(message)
+    }
+
+    //Picks up changes someone else saved since we last loaded or saved the shared file
+    pub fn refresh(&mut self, data: &mut TodoData) -> io::Result<Option<String>> {
+        if self.shared.is_none() {
+            return Ok(None);
+        }
+
+        let lock = lock(&self.path)?;
+        let message = self.merge_changes(data)?;
+        lock.unlock()?;
+        Ok(message)
+    }
+
+    fn merge_changes(&mut self, data: &mut TodoData) -> io::Result<Option<String>> {
+        let base = self.shared.clone().unwrap_or_default();
+        let current = read_contents(&self.path)?;
+        if current == base {
+            return Ok(None);
+        }
+
+        let conflicts = merge::merge(data, parse(&base)?, parse(&current)?);
+        data.history.clear();
+        self.shared = Some(current);
+        let message = if conflicts.is_empty() {
+            String::from("Merged changes saved by someone else, earlier changes can no longer be undone")
+        } else {
+            let ids: Vec<String> = conflicts.iter().map(|id| id.to_string()).collect();
+            format!(
+                "Merged changes saved by someone else, earlier changes can no longer be undone. Both of you changed task {}, your changes were kept",
+                ids.join(", ")
+            )
+        };
+        Ok(Some(message))
+    }
+}
+
+//The data file itself is replaced on every save, so the lock is taken on a file next to it
+fn lock(path: &Path) -> io::Result<fs::File> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut lock_name = path.file_name().unwrap_or_default().to_os_string();
+    lock_name.push(".lock");
+
+    let file = fs::OpenOptions::new().create(true).truncate(false).write(true).open(path.with_file_name(lock_name))?;
+    file.lock_exclusive()?;
+    Ok(file)
+}
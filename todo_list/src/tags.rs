@@ -0,0 +1,97 @@
+use console::style;
+use std::collections::BTreeMap;
+
+use crate::tasks::Task;
+
+//Tags are stored with their prefix: +project or @context. Anything else is treated as a project
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim().to_lowercase();
+    let name = tag.trim_start_matches(['+', '@']);
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return None;
+    }
+
+    if tag.starts_with('+') || tag.starts_with('@') {
+        Some(tag)
+    } else {
+        Some(format!("+{}", tag))
+    }
+}
+
+pub fn parse_tag_list(input: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    for tag in input.split([' ', ',']).filter_map(normalize_tag) {
+        add_tag(&mut tags, tag);
+    }
+    tags
+}
+
+//Splits "+work call the bank @phone" into the name "call the bank" and the tags +work and @phone
+pub fn extract_tags(name: &str) -> (String, Vec<String>) {
+    let mut words = Vec::new();
+    let mut tags = Vec::new();
+
+    for word in name.split_whitespace() {
+        if word.len() > 1 && (word.starts_with('+') || word.starts_with('@')) {
+            add_tag(&mut tags, word.to_lowercase());
+        } else {
+            words.push(word);
+        }
+    }
+
+    (words.join(" "), tags)
+}
+
+pub fn add_tag(tags: &mut Vec<String>, tag: String) {
+    if !tags.contains(&tag) {
+        tags.push(tag);
+    }
+}
+
+pub fn parse_filter(input: &str) -> Vec<String> {
+    input
+        .split([' ', ','])
+        .map(|term| term.trim().to_lowercase())
+        .filter(|term| !term.trim_start_matches(['+', '@']).is_empty())
+        .collect()
+}
+
+//A task matches when it has every tag in the filter. A term without a prefix matches both +term and @term
+pub fn matches_filter(task: &Task, filter: &[String]) -> bool {
+    filter.iter().all(|term| task.tags.iter().any(|tag| tag_matches(tag, term)))
+}
+
+fn tag_matches(tag: &str, term: &str) -> bool {
+    if term.starts_with(['+', '@']) {
+        tag == term
+    } else {
+        tag.trim_start_matches(['+', '@']) == term
+    }
+}
+
+pub fn show_tag_summary(tasks: &[Task]) {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut untagged = 0;
+
+    for task in tasks.iter().filter(|task| !task.completed) {
+        if task.tags.is_empty() {
+            untagged += 1;
+        }
+        for tag in &task.tags {
+            *counts.entry(tag).or_insert(0) += 1;
+        }
+    }
+
+    if counts.is_empty() && untagged == 0 {
+        println!("{}", style("You have no open tasks").blue());
+        return;
+    }
+
+    let width = counts.keys().map(|tag| tag.len()).max().unwrap_or(0).max("untagged".len());
+    for (tag, count) in counts {
+        println!("{:<width$}  {}", style(tag).cyan(), count, width = width);
+    }
+    if untagged > 0 {
+        println!("{:<width$}  {}", style("untagged").dim(), untagged, width = width);
+    }
+}
@@ -0,0 +1,535 @@
+use chrono::{DateTime, Local, NaiveDateTime};
+use clap::ValueEnum;
+use console::style;
+use console::Style;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::fmt;
+
+use crate::due;
+use crate::due::{Due, DueStatus, Recurrence};
+use crate::stats;
+use crate::storage::TodoData;
+use crate::tags;
+use crate::timer;
+use crate::timer::TimeEntry;
+
+#[derive(Serialize, Deserialize, ValueEnum, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Urgent,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 4] = [Priority::Low, Priority::Normal, Priority::High, Priority::Urgent];
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Priority::Low => write!(f, "low"),
+            Priority::Normal => write!(f, "normal"),
+            Priority::High => write!(f, "high"),
+            Priority::Urgent => write!(f, "urgent"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ValueEnum, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Due,
+    Priority,
+    Created,
+    Name,
+    //The order tasks were put in with move
+    Manual,
+}
+
+impl SortOrder {
+    pub const ALL: [SortOrder; 5] = [SortOrder::Due, SortOrder::Priority, SortOrder::Created, SortOrder::Name, SortOrder::Manual];
+}
+
+impl fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            SortOrder::Due => write!(f, "due date"),
+            SortOrder::Priority => write!(f, "priority"),
+            SortOrder::Created => write!(f, "creation"),
+            SortOrder::Name => write!(f, "name"),
+            SortOrder::Manual => write!(f, "manual order"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Task {
+    //Lists saved before ids existed load with 0 and are given ids when loaded
+    #[serde(default)]
+    pub id: u32,
+    pub name: String,
+    pub completed: bool,
+    #[serde(default = "Local::now")]
+    pub created: DateTime<Local>,
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Local>>,
+    #[serde(default)]
+    pub due: Option<Due>,
+    #[serde(default)]
+    pub priority: Priority,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    //The id of the task this is a subtask of
+    #[serde(default)]
+    pub parent: Option<u32>,
+    #[serde(default)]
+    pub repeat: Option<Recurrence>,
+    //Longer free-form text, possibly over several lines
+    #[serde(default)]
+    pub notes: Option<String>,
+    //Intervals worked on the task, the last one open while its timer runs
+    #[serde(default)]
+    pub time: Vec<TimeEntry>,
+}
+
+impl Task {
+    //Any +project or @context words in the name become tags
+    pub fn new(id: u32, name: String) -> Task {
+        let (name, tags) = tags::extract_tags(&name);
+        Task {
+            id,
+            name,
+            completed: false,
+            created: Local::now(),
+            completed_at: None,
+            due: None,
+            priority: Priority::Normal,
+            tags,
+            parent: None,
+            repeat: None,
+            notes: None,
+            time: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Move {
+    Up,
+    Down,
+    Top,
+    Bottom,
+    //A position counting from 1
+    To(usize),
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Move::Up => write!(f, "up"),
+            Move::Down => write!(f, "down"),
+            Move::Top => write!(f, "to the top"),
+            Move::Bottom => write!(f, "to the bottom"),
+            Move::To(position) => write!(f, "to position {}", position),
+        }
+    }
+}
+
+//Accepts "up", "down", "top", "bottom" or a position such as "3"
+pub fn parse_move(input: &str) -> Result<Move, String> {
+    match input.trim().to_lowercase().as_str() {
+        "up" => Ok(Move::Up),
+        "down" => Ok(Move::Down),
+        "top" => Ok(Move::Top),
+        "bottom" => Ok(Move::Bottom),
+        position => match position.parse::<usize>() {
+            Ok(position) if position > 0 => Ok(Move::To(position)),
+            _ => Err(String::from("Move a task up, down, top, bottom or to a position such as 3")),
+        },
+    }
+}
+
+impl fmt::Display for Task {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+pub fn show_tasks(tasks: &[Task], sort: SortOrder, filter: &[String]) {
+    if tasks.is_empty() {
+        println!("{}", style("You have no tasks").blue());
+        return;
+    }
+
+    if !tasks.iter().any(|task| tags::matches_filter(task, filter)) {
+        println!("{}", style("No tasks match the filter").blue());
+        return;
+    }
+
+    let now = due::now();
+    for (task, depth) in tree_order(tasks, sort, filter) {
+        print_task(task, tasks, depth, now);
+    }
+}
+
+//The tasks to show in order, each with how deeply it is nested. Subtasks follow their parent,
+//unless the parent is hidden by the filter
+pub fn tree_order<'a>(tasks: &'a [Task], sort: SortOrder, filter: &[String]) -> Vec<(&'a Task, usize)> {
+    let sorted = sort_tasks(tasks, sort, filter);
+    let mut ordered = Vec::new();
+    for task in sorted.iter().filter(|task| !sorted.iter().any(|other| Some(other.id) == task.parent)) {
+        add_tree(task, &sorted, 0, &mut ordered);
+    }
+    ordered
+}
+
+fn add_tree<'a>(task: &'a Task, shown: &[&'a Task], depth: usize, ordered: &mut Vec<(&'a Task, usize)>) {
+    ordered.push((task, depth));
+    for child in shown.iter().filter(|child| child.parent == Some(task.id)) {
+        add_tree(child, shown, depth + 1, ordered);
+    }
+}
+
+//Tasks without a due date go after those with one, and ties fall back to the most important first
+pub fn sort_tasks<'a>(tasks: &'a [Task], sort: SortOrder, filter: &[String]) -> Vec<&'a Task> {
+    let mut sorted: Vec<&Task> = tasks.iter().filter(|task| tags::matches_filter(task, filter)).collect();
+    match sort {
+        SortOrder::Due => sorted.sort_by_key(|task| (task.due.is_none(), task.due, Reverse(task.priority))),
+        SortOrder::Priority => sorted.sort_by_key(|task| (Reverse(task.priority), task.due.is_none(), task.due)),
+        SortOrder::Created => sorted.sort_by_key(|task| (task.created, task.id)),
+        SortOrder::Name => sorted.sort_by_key(|task| task.name.to_lowercase()),
+        SortOrder::Manual => {}
+    }
+    sorted
+}
+
+pub fn show_due_tasks(tasks: &[Task], days: u32, filter: &[String]) {
+    let now = due::now();
+    let mut due_tasks: Vec<&Task> = tasks
+        .iter()
+        .filter(|task| !task.completed && task.due.is_some_and(|due| due.is_within(now.date(), days)))
+        .filter(|task| tags::matches_filter(task, filter))
+        .collect();
+
+    if due_tasks.is_empty() {
+        println!("{}", style(format!("No tasks are due in the next {} days", days)).blue());
+        return;
+    }
+
+    due_tasks.sort_by_key(|task| (task.due, Reverse(task.priority)));
+    for task in due_tasks {
+        print_task(task, tasks, 0, now);
+    }
+}
+
+//Completed tasks and tasks without a due date have no status or label
+pub fn due_label(task: &Task, now: NaiveDateTime) -> (Option<DueStatus>, String) {
+    match task.due {
+        _ if task.completed => (None, String::new()),
+        None => (None, String::new()),
+        Some(due) => match due.status(now) {
+            DueStatus::Overdue => (Some(DueStatus::Overdue), format!(" (overdue, due {})", due)),
+            DueStatus::Today => match due.time {
+                Some(time) => (Some(DueStatus::Today), format!(" (due today at {})", time.format("%H:%M"))),
+                None => (Some(DueStatus::Today), String::from(" (due today)")),
+            },
+            DueStatus::Upcoming => (Some(DueStatus::Upcoming), format!(" (due {})", due)),
+        },
+    }
+}
+
+pub fn status_style(task: &Task, status: Option<&DueStatus>) -> Style {
+    match status {
+        _ if task.completed => Style::new().green().strikethrough(),
+        None => Style::new().red(),
+        Some(DueStatus::Overdue) => Style::new().magenta().bold(),
+        Some(DueStatus::Today) => Style::new().yellow(),
+        Some(DueStatus::Upcoming) => Style::new().cyan(),
+    }
+}
+
+fn print_task(task: &Task, tasks: &[Task], depth: usize, now: NaiveDateTime) {
+    let (status, label) = due_label(task, now);
+    let name_style = status_style(task, status.as_ref());
+    let priority = match task.priority {
+        Priority::Normal => String::new(),
+        Priority::Low => format!("{} ", style("[low]").dim()),
+        Priority::High => format!("{} ", style("[high]").yellow().bold()),
+        Priority::Urgent => format!("{} ", style("[urgent]").red().bold()),
+    };
+    let progress = match subtask_progress(tasks, task.id) {
+        Some((done, total)) => format!(" {}", style(format!("[{}/{} done]", done, total)).dim()),
+        None => String::new(),
+    };
+    let repeat = match &task.repeat {
+        Some(repeat) if !task.completed => format!(" {}", style(format!("[repeats {}]", repeat)).dim()),
+        _ => String::new(),
+    };
+    let notes = if task.notes.is_some() { format!(" {}", style("[notes]").dim()) } else { String::new() };
+    let time = match timer::running(task) {
+        Some(running) => format!(" {}", style(format!("[timing {}, {} total]", timer::format_clock(running), stats::format_duration(timer::tracked(task)))).yellow()),
+        None if !task.time.is_empty() => format!(" {}", style(format!("[{} tracked]", stats::format_duration(timer::tracked(task)))).dim()),
+        None => String::new(),
+    };
+    let tags = if task.tags.is_empty() { String::new() } else { format!(" {}", style(task.tags.join(" ")).cyan().dim()) };
+    let indent = "  ".repeat(depth);
+    println!("{:>3}. {}{}{}{}{}{}{}{}{}", task.id, indent, priority, name_style.apply_to(&task.name), progress, notes, time, tags, name_style.apply_to(label).dim(), repeat);
+}
+
+//Everything about one task, including its notes
+pub fn show_task_details(tasks: &[Task], index: usize) {
+    let task = &tasks[index];
+    let (status, _) = due_label(task, due::now());
+    println!("{}", status_style(task, status.as_ref()).bold().apply_to(format!("{}. {}", task.id, task.name)));
+
+    match task.completed_at {
+        Some(completed_at) if task.completed => print_detail("Status", format!("completed {}", completed_at.format("%Y-%m-%d %H:%M"))),
+        _ if task.completed => print_detail("Status", String::from("completed")),
+        _ => print_detail("Status", String::from("open")),
+    }
+    print_detail("Created", task.created.format("%Y-%m-%d %H:%M").to_string());
+    if let Some(due) = task.due {
+        let when = match status {
+            Some(DueStatus::Overdue) => " (overdue)",
+            Some(DueStatus::Today) => " (today)",
+            _ => "",
+        };
+        print_detail("Due", format!("{}{}", due, when));
+    }
+    print_detail("Priority", task.priority.to_string());
+    if !task.tags.is_empty() {
+        print_detail("Tags", task.tags.join(" "));
+    }
+    if let Some(repeat) = &task.repeat {
+        print_detail("Repeats", repeat.to_string());
+    }
+    if let Some(parent) = task.parent.and_then(|parent| find_task(tasks, parent)) {
+        print_detail("Subtask of", format!("{}. {}", tasks[parent].id, tasks[parent].name));
+    }
+    if let Some((done, total)) = subtask_progress(tasks, task.id) {
+        print_detail("Subtasks", format!("{}/{} done", done, total));
+    }
+    if !task.time.is_empty() {
+        let running = timer::running(task).map(|running| format!(", timer running for {}", timer::format_clock(running))).unwrap_or_default();
+        let entries = if task.time.len() == 1 { String::from("1 entry") } else { format!("{} entries", task.time.len()) };
+        print_detail("Tracked", format!("{} over {}{}", stats::format_duration(timer::tracked(task)), entries, running));
+    }
+
+    println!();
+    match &task.notes {
+        Some(notes) => println!("{}", notes),
+        None => println!("{}", style("No notes").dim()),
+    }
+}
+
+fn print_detail(label: &str, value: String) {
+    println!("{} {}", style(format!("{:<10}", label)).dim(), value);
+}
+
+//How many of a task's direct subtasks are done, or None if it has no subtasks
+pub fn subtask_progress(tasks: &[Task], id: u32) -> Option<(usize, usize)> {
+    let children: Vec<&Task> = tasks.iter().filter(|task| task.parent == Some(id)).collect();
+    if children.is_empty() {
+        return None;
+    }
+    Some((children.iter().filter(|task| task.completed).count(), children.len()))
+}
+
+//The positions of every subtask below a task, however deeply nested
+pub fn descendants(tasks: &[Task], id: u32) -> Vec<usize> {
+    let mut found = Vec::new();
+    let mut seen = vec![id];
+    let mut parents = vec![id];
+
+    while let Some(parent) = parents.pop() {
+        for (index, task) in tasks.iter().enumerate() {
+            if task.parent == Some(parent) && !seen.contains(&task.id) {
+                seen.push(task.id);
+                found.push(index);
+                parents.push(task.id);
+            }
+        }
+    }
+    found
+}
+
+//Whether following a task's parents leads back to the task itself
+pub fn in_parent_loop(tasks: &[Task], index: usize) -> bool {
+    let mut seen = Vec::new();
+    let mut parent = tasks[index].parent;
+    while let Some(id) = parent {
+        if id == tasks[index].id {
+            return true;
+        }
+        if seen.contains(&id) {
+            return false;
+        }
+        seen.push(id);
+        parent = find_task(tasks, id).and_then(|found| tasks[found].parent);
+    }
+    false
+}
+
+//A task can't be moved under itself or one of its own subtasks
+pub fn can_be_parent(tasks: &[Task], index: usize, parent_id: u32) -> bool {
+    tasks[index].id != parent_id && !descendants(tasks, tasks[index].id).iter().any(|&child| tasks[child].id == parent_id)
+}
+
+pub fn find_task(tasks: &[Task], id: u32) -> Option<usize> {
+    tasks.iter().position(|task| task.id == id)
+}
+
+pub fn add_task(tasks: &mut Vec<Task>, id: u32, name: String) -> &mut Task {
+    tasks.push(Task::new(id, name));
+    tasks.last_mut().unwrap()
+}
+
+//Completing the last open subtask of a task completes the parent as well. Completing a repeating
+//task adds its next occurrence to the list
+pub fn complete_task(data: &mut TodoData, index: usize) {
+    let task = &mut data.tasks[index];
+    if task.completed {
+        return;
+    }
+    task.completed = true;
+    task.completed_at = Some(Local::now());
+    timer::stop_task(task);
+
+    if let Some(repeat) = task.repeat.clone() {
+        let mut next = Task::new(0, task.name.clone());
+        next.due = repeat.next_due(task.due, due::now().date());
+        next.priority = task.priority;
+        next.tags = task.tags.clone();
+        next.parent = task.parent;
+        next.repeat = Some(repeat);
+        next.id = data.next_id();
+        data.tasks.push(next);
+    }
+
+    let parent = data.tasks[index].parent;
+    if let Some(parent_index) = parent.and_then(|parent| find_task(&data.tasks, parent)) {
+        if subtask_progress(&data.tasks, data.tasks[parent_index].id).is_some_and(|(done, total)| done == total) {
+            complete_task(data, parent_index);
+        }
+    }
+}
+
+pub fn complete_subtasks(data: &mut TodoData, index: usize) {
+    let subtasks: Vec<u32> = descendants(&data.tasks, data.tasks[index].id).iter().map(|&child| data.tasks[child].id).collect();
+    for id in subtasks {
+        if let Some(child) = find_task(&data.tasks, id) {
+            complete_task(data, child);
+        }
+    }
+}
+
+//Tasks are looked up by id as they are completed, since completing one can add others to the list
+pub fn complete_tasks(data: &mut TodoData, ids: &[u32], with_subtasks: bool) {
+    for &id in ids {
+        if let Some(index) = find_task(&data.tasks, id) {
+            if with_subtasks {
+                complete_subtasks(data, index);
+            }
+            complete_task(data, index);
+        }
+    }
+}
+
+pub fn has_open_subtasks(tasks: &[Task], index: usize) -> bool {
+    descendants(tasks, tasks[index].id).iter().any(|&child| !tasks[child].completed)
+}
+
+pub fn edit_task(tasks: &mut [Task], index: usize, name: String) {
+    let (name, new_tags) = tags::extract_tags(&name);
+    tasks[index].name = name;
+    for tag in new_tags {
+        tags::add_tag(&mut tasks[index].tags, tag);
+    }
+}
+
+pub fn set_tags(tasks: &mut [Task], index: usize, tags: Vec<String>) {
+    tasks[index].tags = tags;
+}
+
+pub fn set_due(tasks: &mut [Task], index: usize, due: Option<Due>) {
+    tasks[index].due = due;
+}
+
+pub fn set_priority(tasks: &mut [Task], index: usize, priority: Priority) {
+    tasks[index].priority = priority;
+}
+
+pub fn set_repeat(tasks: &mut [Task], index: usize, repeat: Option<Recurrence>) {
+    tasks[index].repeat = repeat;
+}
+
+//Blank notes are removed altogether
+pub fn set_notes(tasks: &mut [Task], index: usize, notes: Option<String>) {
+    tasks[index].notes = notes.map(|notes| notes.trim_end().to_string()).filter(|notes| !notes.trim().is_empty());
+}
+
+pub fn set_parent(tasks: &mut [Task], index: usize, parent: Option<u32>) {
+    tasks[index].parent = parent;
+}
+
+//Tasks are reordered among the other tasks with the same parent, so subtasks stay with their parent.
+//Returns the task's new position among them, counting from 1
+pub fn move_task(tasks: &mut [Task], index: usize, to: Move) -> usize {
+    let parent = tasks[index].parent;
+    let slots: Vec<usize> = (0..tasks.len()).filter(|&slot| tasks[slot].parent == parent).collect();
+    let mut siblings: Vec<Task> = slots.iter().map(|&slot| tasks[slot].clone()).collect();
+
+    let from = slots.iter().position(|&slot| slot == index).unwrap();
+    let target = match to {
+        Move::Up => from.saturating_sub(1),
+        Move::Down => from + 1,
+        Move::Top => 0,
+        Move::Bottom => siblings.len(),
+        Move::To(position) => position - 1,
+    };
+    let task = siblings.remove(from);
+    let target = target.min(siblings.len());
+    siblings.insert(target, task);
+
+    for (slot, task) in slots.into_iter().zip(siblings) {
+        tasks[slot] = task;
+    }
+    target + 1
+}
+
+//Deleting a task deletes its subtasks with it
+pub fn delete_task(tasks: &mut Vec<Task>, index: usize) {
+    let mut removed: Vec<u32> = descendants(tasks, tasks[index].id).iter().map(|&child| tasks[child].id).collect();
+    removed.push(tasks[index].id);
+    tasks.retain(|task| !removed.contains(&task.id));
+}
+
+//A task may already have gone with an earlier task's subtasks
+pub fn delete_tasks(tasks: &mut Vec<Task>, ids: &[u32]) {
+    for &id in ids {
+        if let Some(index) = find_task(tasks, id) {
+            delete_task(tasks, index);
+        }
+    }
+}
+
+//Open subtasks of a removed task move up to the removed task's own parent. Returns the removed tasks
+pub fn remove_completed_tasks(tasks: &mut Vec<Task>) -> Vec<Task> {
+    let (completed, open): (Vec<Task>, Vec<Task>) = tasks.drain(..).partition(|task| task.completed);
+    *tasks = open;
+    for task in tasks.iter_mut() {
+        while let Some(removed) = completed.iter().find(|removed| Some(removed.id) == task.parent) {
+            task.parent = removed.parent;
+        }
+    }
+    completed
+}
+
+pub fn clear_tasks(tasks: &mut Vec<Task>) {
+    tasks.clear();
+}
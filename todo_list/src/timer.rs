@@ -0,0 +1,175 @@
+use chrono::{DateTime, Duration, Local, NaiveDate};
+use clap::ValueEnum;
+use console::{style, Term};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::thread;
+use std::time;
+
+use crate::stats;
+use crate::storage::TodoData;
+use crate::tasks::Task;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct TimeEntry {
+    pub start: DateTime<Local>,
+    //None while the timer is still running
+    #[serde(default)]
+    pub end: Option<DateTime<Local>>,
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Default)]
+pub enum Timesheet {
+    //Total time for each task
+    #[default]
+    Task,
+    //Time for each task on each day
+    Day,
+}
+
+impl Timesheet {
+    pub const ALL: [Timesheet; 2] = [Timesheet::Task, Timesheet::Day];
+}
+
+impl fmt::Display for Timesheet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Timesheet::Task => write!(f, "per task"),
+            Timesheet::Day => write!(f, "per day"),
+        }
+    }
+}
+
+//All the time tracked against a task, including a timer that is still running
+pub fn tracked(task: &Task) -> Duration {
+    let now = Local::now();
+    task.time.iter().map(|entry| entry.end.unwrap_or(now) - entry.start).sum()
+}
+
+//How long the task's timer has been running, if it is
+pub fn running(task: &Task) -> Option<Duration> {
+    task.time.iter().find(|entry| entry.end.is_none()).map(|entry| Local::now() - entry.start)
+}
+
+//The task with a running timer, from any list
+pub fn active_task(data: &TodoData) -> Option<&Task> {
+    data.tasks.iter().chain(data.lists.values().flatten()).find(|task| running(task).is_some())
+}
+
+//Only one task is timed at once, so starting a timer stops any other. Returns the task stopped and for how long
+pub fn start(data: &mut TodoData, index: usize) -> Result<Option<(String, Duration)>, String> {
+    let task = &data.tasks[index];
+    if task.completed {
+        return Err(format!("{} is already completed", task.name));
+    }
+    if running(task).is_some() {
+        return Err(format!("{} is already being timed", task.name));
+    }
+
+    let stopped = stop(data);
+    data.tasks[index].time.push(TimeEntry { start: Local::now(), end: None });
+    Ok(stopped)
+}
+
+//Stops the running timer and returns the task's name and how long this interval was
+pub fn stop(data: &mut TodoData) -> Option<(String, Duration)> {
+    let task = data.tasks.iter_mut().chain(data.lists.values_mut().flatten()).find(|task| running(task).is_some())?;
+    let elapsed = stop_task(task)?;
+    Some((task.name.clone(), elapsed))
+}
+
+pub fn stop_task(task: &mut Task) -> Option<Duration> {
+    let entry = task.time.iter_mut().find(|entry| entry.end.is_none())?;
+    let end = Local::now();
+    entry.end = Some(end);
+    Some(end - entry.start)
+}
+
+//Running timers are shown as a clock such as "0:12:05"
+pub fn format_clock(duration: Duration) -> String {
+    let seconds = duration.num_seconds().max(0);
+    format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+pub fn timer_line(task: &Task) -> String {
+    let running = running(task).unwrap_or_else(Duration::zero);
+    format!(
+        "{} {}. {} {}",
+        style(format_clock(running)).yellow().bold(),
+        task.id,
+        task.name,
+        style(format!("({} total)", stats::format_duration(tracked(task)))).dim()
+    )
+}
+
+//Redraws the timer every second until the program is interrupted
+pub fn watch_timer(task: &Task) -> io::Result<()> {
+    let term = Term::stdout();
+    loop {
+        term.clear_line()?;
+        term.write_str(&timer_line(task))?;
+        thread::sleep(time::Duration::from_secs(1));
+    }
+}
+
+//Intervals are counted on the day they started
+pub fn export_csv(tasks: &[&Task], list: &str, report: Timesheet) -> String {
+    let now = Local::now();
+    let mut output = String::new();
+
+    match report {
+        Timesheet::Task => {
+            output.push_str("task_id,task,list,entries,minutes,hours\n");
+            for task in tasks.iter().filter(|task| !task.time.is_empty()) {
+                let tracked = tracked(task);
+                output.push_str(&format!(
+                    "{},{},{},{},{},{:.2}\n",
+                    task.id,
+                    csv_field(&task.name),
+                    csv_field(list),
+                    task.time.len(),
+                    tracked.num_minutes(),
+                    hours(tracked)
+                ));
+            }
+        }
+        Timesheet::Day => {
+            let mut days: BTreeMap<(NaiveDate, u32), (String, Duration)> = BTreeMap::new();
+            for task in tasks {
+                for entry in &task.time {
+                    let day = days.entry((entry.start.date_naive(), task.id)).or_insert((task.name.clone(), Duration::zero()));
+                    day.1 += entry.end.unwrap_or(now) - entry.start;
+                }
+            }
+
+            output.push_str("date,task_id,task,list,minutes,hours\n");
+            for ((date, id), (name, duration)) in days {
+                output.push_str(&format!(
+                    "{},{},{},{},{},{:.2}\n",
+                    date.format("%Y-%m-%d"),
+                    id,
+                    csv_field(&name),
+                    csv_field(list),
+                    duration.num_minutes(),
+                    hours(duration)
+                ));
+            }
+        }
+    }
+    output
+}
+
+fn hours(duration: Duration) -> f64 {
+    duration.num_seconds() as f64 / 3600.0
+}
+
+//Fields with commas, quotes or line breaks are quoted, with any quotes doubled
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,365 @@
+use std::io;
+use std::time::Duration;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Position};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::archive;
+use crate::due;
+use crate::due::DueStatus;
+use crate::history;
+use crate::stats;
+use crate::storage::{Store, TodoData};
+use crate::tasks;
+use crate::tasks::{Move, Priority, SortOrder, Task};
+use crate::timer;
+
+const PAGE_SIZE: usize = 10;
+const TICK: Duration = Duration::from_secs(1);
+const KEY_HELP: &str = "a add  s subtask  e edit  space complete  d delete  J/K move  t timer  x clean  u undo  r redo  o sort  q quit";
+
+enum Mode {
+    Normal,
+    Input { action: InputAction, text: String, cursor: usize },
+    ConfirmDelete(u32),
+}
+
+enum InputAction {
+    Add,
+    AddSubtask(u32),
+    Edit(u32),
+}
+
+struct App<'a> {
+    data: &'a mut TodoData,
+    store: &'a mut Store,
+    list: ListState,
+    mode: Mode,
+    message: String,
+    quit: bool,
+}
+
+//Takes over the terminal until the user quits, saving after every change just like the menu does
+pub fn run(data: &mut TodoData, store: &mut Store) -> io::Result<()> {
+    let mut terminal = ratatui::init();
+    let mut app = App {
+        data,
+        store,
+        list: ListState::default().with_selected(Some(0)),
+        mode: Mode::Normal,
+        message: String::new(),
+        quit: false,
+    };
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl App<'_> {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame))?;
+            //Redraw every second even without a key press so a running timer keeps counting
+            if !event::poll(TICK)? {
+                continue;
+            }
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    self.handle_key(key);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    //The ids of the tasks in the order they are shown
+    fn visible_ids(&self) -> Vec<u32> {
+        tasks::tree_order(&self.data.tasks, self.data.sort, &[]).iter().map(|(task, _)| task.id).collect()
+    }
+
+    fn selected_id(&self) -> Option<u32> {
+        self.list.selected().and_then(|selected| self.visible_ids().get(selected).copied())
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [header_area, list_area, footer_area] = Layout::vertical([Constraint::Length(1), Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+
+        let open = self.data.tasks.iter().filter(|task| !task.completed).count();
+        let mut header = format!(" todo_list [{}]  {} open, {} done  (sorted by {})", self.data.current_list, open, self.data.tasks.len() - open, self.data.sort);
+        if let Some(task) = timer::active_task(self.data) {
+            header.push_str(&format!("  timing {} {}", task.name, timer::format_clock(timer::running(task).unwrap())));
+        }
+        frame.render_widget(Paragraph::new(header).style(Style::new().fg(Color::Blue).add_modifier(Modifier::BOLD)), header_area);
+
+        let now = due::now();
+        let items: Vec<ListItem> = tasks::tree_order(&self.data.tasks, self.data.sort, &[])
+            .into_iter()
+            .map(|(task, depth)| ListItem::new(task_line(task, &self.data.tasks, depth, now)))
+            .collect();
+        let list = if items.is_empty() {
+            List::new([ListItem::new(Span::styled("You have no tasks", Style::new().fg(Color::Blue)))])
+        } else {
+            List::new(items)
+        };
+        let list = list
+            .block(Block::default().borders(Borders::ALL).title(" Tasks "))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, list_area, &mut self.list);
+
+        match &self.mode {
+            Mode::Normal => {
+                let footer = if self.message.is_empty() { KEY_HELP } else { &self.message };
+                frame.render_widget(Paragraph::new(footer).style(Style::new().fg(Color::Blue)), footer_area);
+            }
+            Mode::ConfirmDelete(id) => {
+                let prompt = format!("Delete task {} and its subtasks? (y/n)", id);
+                frame.render_widget(Paragraph::new(prompt).style(Style::new().fg(Color::Yellow)), footer_area);
+            }
+            Mode::Input { action, text, cursor } => {
+                let prompt = match action {
+                    InputAction::Add => String::from("New task: "),
+                    InputAction::AddSubtask(id) => format!("New subtask of {}: ", id),
+                    InputAction::Edit(id) => format!("Rename task {}: ", id),
+                };
+                let cursor_x = footer_area.x + (prompt.chars().count() + cursor) as u16;
+                frame.render_widget(Paragraph::new(format!("{}{}", prompt, text)), footer_area);
+                frame.set_cursor_position(Position::new(cursor_x.min(footer_area.right().saturating_sub(1)), footer_area.y));
+            }
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        match self.mode {
+            Mode::Normal => self.handle_normal_key(key),
+            Mode::ConfirmDelete(id) => {
+                if key.code == KeyCode::Char('y') {
+                    if let Some(index) = tasks::find_task(&self.data.tasks, id) {
+                        self.change("delete", |data| tasks::delete_task(&mut data.tasks, index));
+                    }
+                }
+                self.mode = Mode::Normal;
+            }
+            Mode::Input { .. } => self.handle_input_key(key),
+        }
+    }
+
+    fn handle_normal_key(&mut self, key: KeyEvent) {
+        self.message.clear();
+        let count = self.visible_ids().len();
+        let selected = self.list.selected().unwrap_or(0);
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Down | KeyCode::Char('j') => self.select(selected + 1, count),
+            KeyCode::Up | KeyCode::Char('k') => self.select(selected.saturating_sub(1), count),
+            KeyCode::PageDown => self.select(selected + PAGE_SIZE, count),
+            KeyCode::PageUp => self.select(selected.saturating_sub(PAGE_SIZE), count),
+            KeyCode::Home | KeyCode::Char('g') => self.select(0, count),
+            KeyCode::End | KeyCode::Char('G') => self.select(count.saturating_sub(1), count),
+            KeyCode::Char('a') => self.start_input(InputAction::Add, String::new()),
+            KeyCode::Char('s') => {
+                if let Some(id) = self.selected_id() {
+                    self.start_input(InputAction::AddSubtask(id), String::new());
+                }
+            }
+            KeyCode::Char('e') => {
+                if let Some(id) = self.selected_id() {
+                    let name = self.data.tasks[tasks::find_task(&self.data.tasks, id).unwrap()].name.clone();
+                    self.start_input(InputAction::Edit(id), name);
+                }
+            }
+            KeyCode::Char(' ') | KeyCode::Enter | KeyCode::Char('c') => {
+                if let Some(index) = self.selected_id().and_then(|id| tasks::find_task(&self.data.tasks, id)) {
+                    self.change("complete", |data| tasks::complete_task(data, index));
+                }
+            }
+            KeyCode::Char('d') | KeyCode::Delete => {
+                if let Some(id) = self.selected_id() {
+                    self.mode = Mode::ConfirmDelete(id);
+                }
+            }
+            KeyCode::Char('J') | KeyCode::Char('K') => {
+                if let Some(id) = self.selected_id() {
+                    let to = if key.code == KeyCode::Char('K') { Move::Up } else { Move::Down };
+                    let index = tasks::find_task(&self.data.tasks, id).unwrap();
+                    self.change("move", |data| {
+                        tasks::move_task(&mut data.tasks, index, to);
+                        data.sort = SortOrder::Manual;
+                    });
+                    let selected = self.visible_ids().iter().position(|visible| *visible == id);
+                    self.list.select(selected);
+                }
+            }
+            KeyCode::Char('t') => {
+                if let Some(index) = self.selected_id().and_then(|id| tasks::find_task(&self.data.tasks, id)) {
+                    if timer::running(&self.data.tasks[index]).is_some() {
+                        self.change("stop", |data| {
+                            timer::stop(data);
+                        });
+                    } else {
+                        let mut result = Ok(None);
+                        self.change("start", |data| result = timer::start(data, index));
+                        if let Err(message) = result {
+                            self.message = message;
+                        }
+                    }
+                }
+            }
+            KeyCode::Char('x') => {
+                let count = archive::clean(self.data);
+                self.save();
+                self.message = format!("Archived {} completed tasks", count);
+            }
+            KeyCode::Char('u') => {
+                self.message = match history::undo(self.data) {
+                    Some(action) => format!("Undid {}", action),
+                    None => String::from("Nothing to undo"),
+                };
+                self.save();
+            }
+            KeyCode::Char('r') => {
+                self.message = match history::redo(self.data) {
+                    Some(action) => format!("Redid {}", action),
+                    None => String::from("Nothing to redo"),
+                };
+                self.save();
+            }
+            KeyCode::Char('o') => {
+                let current = SortOrder::ALL.iter().position(|sort| *sort == self.data.sort).unwrap();
+                self.data.sort = SortOrder::ALL[(current + 1) % SortOrder::ALL.len()];
+                self.message = format!("Sorted by {}", self.data.sort);
+                self.save();
+            }
+            _ => {}
+        }
+
+        let count = self.visible_ids().len();
+        self.select(self.list.selected().unwrap_or(0), count);
+    }
+
+    fn handle_input_key(&mut self, key: KeyEvent) {
+        let Mode::Input { action, text, cursor } = &mut self.mode else {
+            return;
+        };
+        let byte_index = |text: &str, cursor: usize| text.char_indices().nth(cursor).map(|(index, _)| index).unwrap_or(text.len());
+
+        match key.code {
+            KeyCode::Esc => self.mode = Mode::Normal,
+            KeyCode::Enter => {
+                let text = text.trim().to_string();
+                let action = std::mem::replace(action, InputAction::Add);
+                self.mode = Mode::Normal;
+                if !text.is_empty() {
+                    self.submit_input(action, text);
+                }
+            }
+            KeyCode::Char(c) => {
+                text.insert(byte_index(text, *cursor), c);
+                *cursor += 1;
+            }
+            KeyCode::Backspace if *cursor > 0 => {
+                *cursor -= 1;
+                text.remove(byte_index(text, *cursor));
+            }
+            KeyCode::Delete if *cursor < text.chars().count() => {
+                text.remove(byte_index(text, *cursor));
+            }
+            KeyCode::Left => *cursor = cursor.saturating_sub(1),
+            KeyCode::Right => *cursor = (*cursor + 1).min(text.chars().count()),
+            KeyCode::Home => *cursor = 0,
+            KeyCode::End => *cursor = text.chars().count(),
+            _ => {}
+        }
+    }
+
+    fn start_input(&mut self, action: InputAction, text: String) {
+        let cursor = text.chars().count();
+        self.mode = Mode::Input { action, text, cursor };
+    }
+
+    fn submit_input(&mut self, action: InputAction, text: String) {
+        match action {
+            InputAction::Add => self.change("add", |data| {
+                let id = data.next_id();
+                tasks::add_task(&mut data.tasks, id, text);
+            }),
+            InputAction::AddSubtask(parent) => self.change("add", |data| {
+                let id = data.next_id();
+                tasks::add_task(&mut data.tasks, id, text).parent = Some(parent);
+            }),
+            InputAction::Edit(id) => {
+                if let Some(index) = tasks::find_task(&self.data.tasks, id) {
+                    self.change("edit", |data| tasks::edit_task(&mut data.tasks, index, text));
+                }
+            }
+        }
+    }
+
+    //Applies a change to the list, recording it for undo and saving it straight away
+    fn change(&mut self, action: &str, change: impl FnOnce(&mut TodoData)) {
+        let before = self.data.tasks.clone();
+        change(self.data);
+        self.data.history.record(action, &self.data.current_list, before, &self.data.tasks);
+        self.save();
+    }
+
+    fn save(&mut self) {
+        match self.store.save(self.data) {
+            Ok(Some(message)) => self.message = message,
+            Ok(None) => {}
+            Err(err) => self.message = format!("Failed to save tasks: {}", err),
+        }
+    }
+
+    fn select(&mut self, selected: usize, count: usize) {
+        if count == 0 {
+            self.list.select(None);
+        } else {
+            self.list.select(Some(selected.min(count - 1)));
+        }
+    }
+}
+
+//Uses the same colours as the show operation
+fn task_line<'a>(task: &'a Task, tasks: &[Task], depth: usize, now: chrono::NaiveDateTime) -> Line<'a> {
+    let (status, label) = tasks::due_label(task, now);
+    let name_style = match status {
+        _ if task.completed => Style::new().fg(Color::Green).add_modifier(Modifier::CROSSED_OUT),
+        None => Style::new().fg(Color::Red),
+        Some(DueStatus::Overdue) => Style::new().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+        Some(DueStatus::Today) => Style::new().fg(Color::Yellow),
+        Some(DueStatus::Upcoming) => Style::new().fg(Color::Cyan),
+    };
+
+    let mut spans = vec![Span::raw(format!("{:>3}. {}", task.id, "  ".repeat(depth)))];
+    match task.priority {
+        Priority::Normal => {}
+        Priority::Low => spans.push(Span::styled("[low] ", Style::new().add_modifier(Modifier::DIM))),
+        Priority::High => spans.push(Span::styled("[high] ", Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD))),
+        Priority::Urgent => spans.push(Span::styled("[urgent] ", Style::new().fg(Color::Red).add_modifier(Modifier::BOLD))),
+    }
+    spans.push(Span::styled(task.name.as_str(), name_style));
+    if let Some((done, total)) = tasks::subtask_progress(tasks, task.id) {
+        spans.push(Span::styled(format!(" [{}/{} done]", done, total), Style::new().add_modifier(Modifier::DIM)));
+    }
+    if task.notes.is_some() {
+        spans.push(Span::styled(" [notes]", Style::new().add_modifier(Modifier::DIM)));
+    }
+    if let Some(running) = timer::running(task) {
+        spans.push(Span::styled(format!(" [timing {}]", timer::format_clock(running)), Style::new().fg(Color::Yellow)));
+    } else if !task.time.is_empty() {
+        spans.push(Span::styled(format!(" [{} tracked]", stats::format_duration(timer::tracked(task))), Style::new().add_modifier(Modifier::DIM)));
+    }
+    if !task.tags.is_empty() {
+        spans.push(Span::styled(format!(" {}", task.tags.join(" ")), Style::new().fg(Color::Cyan).add_modifier(Modifier::DIM)));
+    }
+    spans.push(Span::styled(label, name_style.add_modifier(Modifier::DIM)));
+    if let Some(repeat) = task.repeat.as_ref().filter(|_| !task.completed) {
+        spans.push(Span::styled(format!(" [repeats {}]", repeat), Style::new().add_modifier(Modifier::DIM)));
+    }
+    Line::from(spans)
+}
@@ -0,0 +1,184 @@
+use serde::Deserialize;
+
+use crate::Season;
+
+//How long each season lasts when a climate file has no calendar
+const DEFAULT_DAYS_PER_SEASON: u32 = 91;
+
+//Where the weather starts on the first day of a new season
+#[derive(Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Transition {
+    //Stay on the same hex of the new season's flower
+    #[default]
+    Keep,
+    //Go back to the centre hex, as changing season by hand does
+    Centre,
+    //Go to any hex on the board
+    Random,
+}
+
+pub struct Month {
+    pub name: String,
+    pub days: u32,
+    pub season: Season,
+}
+
+//A year of months, each in one season. A calendar given as days per season has one month for each season,
+//named after it
+pub struct Calendar {
+    pub months: Vec<Month>,
+    pub named_months: bool,
+    pub transition: Transition,
+}
+
+//The layout of the calendar section of a climate file, before it has been checked
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CalendarFile {
+    days_per_season: Option<u32>,
+    #[serde(default)]
+    months: Vec<MonthFile>,
+    #[serde(default)]
+    transition: Transition,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MonthFile {
+    name: String,
+    days: u32,
+    season: Season,
+}
+
+//Days are counted from 0 at the start of the year
+impl Calendar {
+    pub fn year_length(&self) -> u32 {
+        self.months.iter().map(|month| month.days).sum()
+    }
+
+    //The month a day falls in and the day of that month, counting from 1
+    fn month_of(&self, date: u32) -> (&Month, u32) {
+        let mut day = date % self.year_length();
+        for month in &self.months {
+            if day < month.days {
+                return (month, day + 1);
+            }
+            day -= month.days;
+        }
+        unreachable!("every day of the year is in a month")
+    }
+
+    pub fn season_on(&self, date: u32) -> Season {
+        self.month_of(date).0.season
+    }
+
+    pub fn format_date(&self, date: u32) -> String {
+        let (month, day) = self.month_of(date);
+        if self.named_months {
+            format!("{} {}", day, month.name)
+        } else {
+            format!("{} day {}", month.name, day)
+        }
+    }
+
+    //The first day of the first month in the season, if the calendar has it
+    pub fn start_of_season(&self, season: Season) -> Option<u32> {
+        self.start_of(|month| month.season == season)
+    }
+
+    //A day of the month starting on the given date, counting from 1
+    pub fn day_of_month(&self, month_start: u32, day: u32) -> Result<u32, String> {
+        let (month, _) = self.month_of(month_start);
+        if day == 0 || day > month.days {
+            return Err(format!("{} has {} days", month.name, month.days));
+        }
+        Ok(month_start + day - 1)
+    }
+
+    pub fn start_of_month(&self, name: &str) -> Option<u32> {
+        self.start_of(|month| month.name.eq_ignore_ascii_case(name))
+    }
+
+    fn start_of(&self, wanted: impl Fn(&Month) -> bool) -> Option<u32> {
+        let mut date = 0;
+        for month in &self.months {
+            if wanted(month) {
+                return Some(date);
+            }
+            date += month.days;
+        }
+        None
+    }
+}
+
+pub fn default_calendar() -> Calendar {
+    check_calendar(CalendarFile {
+        days_per_season: Some(DEFAULT_DAYS_PER_SEASON),
+        months: Vec::new(),
+        transition: Transition::Keep,
+    })
+    .unwrap()
+}
+
+//Either days_per_season or a list of months has to be given, but not both
+pub fn check_calendar(file: CalendarFile) -> Result<Calendar, String> {
+    let (months, named_months) = match (file.days_per_season, file.months.is_empty()) {
+        (Some(_), false) => {
+            return Err(String::from(
+                "calendar: give either days_per_season or months, not both",
+            ))
+        }
+        (None, true) => {
+            return Err(String::from(
+                "calendar: give either days_per_season or a list of months",
+            ))
+        }
+        (Some(days), true) => {
+            let months = Season::iterator()
+                .map(|season| Month {
+                    name: season.to_string(),
+                    days,
+                    season: *season,
+                })
+                .collect();
+            (months, false)
+        }
+        (None, false) => {
+            let months: Vec<Month> = file
+                .months
+                .into_iter()
+                .map(|month| Month {
+                    name: month.name,
+                    days: month.days,
+                    season: month.season,
+                })
+                .collect();
+            for (index, month) in months.iter().enumerate() {
+                if month.name.trim().is_empty() {
+                    return Err(format!("calendar: month {} has no name", index + 1));
+                }
+                if months[..index]
+                    .iter()
+                    .any(|other| other.name.eq_ignore_ascii_case(&month.name))
+                {
+                    return Err(format!(
+                        "calendar: there is more than one month called {}",
+                        month.name
+                    ));
+                }
+            }
+            (months, true)
+        }
+    };
+
+    if let Some(month) = months.iter().find(|month| month.days == 0) {
+        return Err(format!("calendar: {} has no days", month.name));
+    }
+
+    Ok(Calendar {
+        months,
+        named_months,
+        transition: file.transition,
+    })
+}
@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::climate;
+use crate::Day;
+
+//Everything needed to carry a campaign's weather on from one session to the next. The last day is the current one
+#[derive(Serialize, Deserialize)]
+pub struct Campaign {
+    //The climate file the campaign was started with, used when --climate is not given
+    #[serde(default)]
+    pub climate: Option<PathBuf>,
+    pub days: Vec<Day>,
+}
+
+//A campaign file that does not exist yet is not an error, it is created when the first day is saved
+pub fn load_campaign(path: &Path) -> Result<Option<Campaign>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = fs::read_to_string(path)
+        .map_err(|err| format!("Could not read {}: {}", path.display(), err))?;
+    let campaign: Campaign =
+        serde_json::from_str(&contents).map_err(|err| format!("{}: {}", path.display(), err))?;
+
+    match campaign.days.last() {
+        None => Err(format!("{}: the campaign has no days", path.display())),
+        Some(day) if !climate::is_on_board(day.hex) => Err(format!(
+            "{}: hex {} of day {} is off the board",
+            path.display(),
+            climate::format_hex(day.hex),
+            day.number
+        )),
+        Some(_) => Ok(Some(campaign)),
+    }
+}
+
+//Written to a temporary file first so a crash part way through can't lose the campaign
+pub fn save_campaign(path: &Path, campaign: &Campaign) -> Result<(), String> {
+    let contents = serde_json::to_string_pretty(campaign).unwrap();
+    let temp_path = path.with_extension("json.tmp");
+
+    fs::File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(contents.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp_path, path))
+        .map_err(|err| format!("Could not save the campaign to {}: {}", path.display(), err))
+}
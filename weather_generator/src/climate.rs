@@ -0,0 +1,232 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::calendar;
+use crate::calendar::{Calendar, CalendarFile};
+use crate::{Direction, Season};
+
+pub type WeatherTable = HashMap<(i32, i32, i32), String>;
+pub type BlockedEdges = HashMap<(i32, i32, i32), Vec<Direction>>;
+
+//The climate used when no file is given, which doubles as an example for writing your own
+const DEFAULT_CLIMATE: &str = include_str!("../climates/default.toml");
+
+const DIRECTION_NAMES: [&str; 6] = [
+    "top_left",
+    "top",
+    "top_right",
+    "bottom_right",
+    "bottom",
+    "bottom_left",
+];
+
+pub struct SeasonClimate {
+    pub weather: WeatherTable,
+    pub blocked: BlockedEdges,
+}
+
+pub struct Climate {
+    pub spring: SeasonClimate,
+    pub summer: SeasonClimate,
+    pub autumn: SeasonClimate,
+    pub winter: SeasonClimate,
+    pub calendar: Calendar,
+}
+
+//The layout of a climate file, before it has been checked
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ClimateFile {
+    spring: SeasonFile,
+    summer: SeasonFile,
+    autumn: SeasonFile,
+    winter: SeasonFile,
+    calendar: Option<CalendarFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SeasonFile {
+    hexes: Vec<HexFile>,
+    #[serde(default)]
+    blocked: Vec<BlockedFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HexFile {
+    hex: (i32, i32, i32),
+    weather: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BlockedFile {
+    hex: (i32, i32, i32),
+    directions: Vec<String>,
+}
+
+pub fn default_climate() -> Climate {
+    parse_climate(DEFAULT_CLIMATE, false).expect("The built in climate is invalid")
+}
+
+//The format is picked from the file extension, .toml or .json
+pub fn load_climate(path: &Path) -> Result<Climate, String> {
+    let json = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => false,
+        Some("json") => true,
+        _ => {
+            return Err(format!(
+                "{}: climate files must end in .toml or .json",
+                path.display()
+            ))
+        }
+    };
+    let contents = fs::read_to_string(path)
+        .map_err(|err| format!("Could not read {}: {}", path.display(), err))?;
+
+    parse_climate(&contents, json).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+fn parse_climate(contents: &str, json: bool) -> Result<Climate, String> {
+    let file: ClimateFile = if json {
+        serde_json::from_str(contents).map_err(|err| err.to_string())?
+    } else {
+        toml::from_str(contents).map_err(|err| err.to_string())?
+    };
+
+    Ok(Climate {
+        spring: check_season(Season::Spring, file.spring)?,
+        summer: check_season(Season::Summer, file.summer)?,
+        autumn: check_season(Season::Autumn, file.autumn)?,
+        winter: check_season(Season::Winter, file.winter)?,
+        calendar: match file.calendar {
+            Some(calendar) => calendar::check_calendar(calendar)?,
+            None => calendar::default_calendar(),
+        },
+    })
+}
+
+//Every hex on the board needs weather, and edges can only be blocked on the outer ring
+fn check_season(season: Season, file: SeasonFile) -> Result<SeasonClimate, String> {
+    let mut weather: WeatherTable = HashMap::new();
+    for entry in file.hexes {
+        check_on_board(season, entry.hex)?;
+        if weather.insert(entry.hex, entry.weather).is_some() {
+            return Err(format!(
+                "{}: hex {} is given more than once",
+                season,
+                format_hex(entry.hex)
+            ));
+        }
+    }
+    for hex in board() {
+        if !weather.contains_key(&hex) {
+            return Err(format!(
+                "{}: no weather for hex {}",
+                season,
+                format_hex(hex)
+            ));
+        }
+    }
+
+    let mut blocked: BlockedEdges = HashMap::new();
+    for entry in file.blocked {
+        check_on_board(season, entry.hex)?;
+        if !is_outer_hex(entry.hex) {
+            return Err(format!(
+                "{}: hex {} is not on the outer ring, so it has no edges to block",
+                season,
+                format_hex(entry.hex)
+            ));
+        }
+        for name in entry.directions {
+            let dir = parse_direction(&name).ok_or(format!(
+                "{}: unknown direction \"{}\" for hex {}, expected one of {}",
+                season,
+                name,
+                format_hex(entry.hex),
+                DIRECTION_NAMES.join(", ")
+            ))?;
+            blocked.entry(entry.hex).or_default().push(dir);
+        }
+    }
+
+    Ok(SeasonClimate { weather, blocked })
+}
+
+fn check_on_board(season: Season, hex: (i32, i32, i32)) -> Result<(), String> {
+    if !is_on_board(hex) {
+        return Err(format!(
+            "{}: hex {} is off the board, coordinates must add up to 0 and be between -2 and 2",
+            season,
+            format_hex(hex)
+        ));
+    }
+    Ok(())
+}
+
+//The 19 hexes of the flower, out to two steps from the centre
+pub fn board() -> Vec<(i32, i32, i32)> {
+    let mut hexes = Vec::new();
+    for x in -2..=2 {
+        for y in -2..=2 {
+            let z: i32 = -x - y;
+            if z.abs() <= 2 {
+                hexes.push((x, y, z));
+            }
+        }
+    }
+    hexes
+}
+
+pub fn is_on_board(hex: (i32, i32, i32)) -> bool {
+    hex.0 + hex.1 + hex.2 == 0 && hex.0.abs().max(hex.1.abs()).max(hex.2.abs()) <= 2
+}
+
+pub fn is_outer_hex(hex: (i32, i32, i32)) -> bool {
+    hex.0.abs() + hex.1.abs() + hex.2.abs() == 4
+}
+
+fn parse_direction(name: &str) -> Option<Direction> {
+    match name.to_lowercase().replace(['-', ' '], "_").as_str() {
+        "top_left" => Some(Direction::TopLeft),
+        "top" => Some(Direction::Top),
+        "top_right" => Some(Direction::TopRight),
+        "bottom_right" => Some(Direction::BottomRight),
+        "bottom" => Some(Direction::Bottom),
+        "bottom_left" => Some(Direction::BottomLeft),
+        _ => None,
+    }
+}
+
+//Hexes are written as x,y,z, with or without brackets
+pub fn parse_hex(text: &str) -> Result<(i32, i32, i32), String> {
+    let coords: Vec<i32> = text
+        .trim_matches(|c| c == '(' || c == ')' || c == '[' || c == ']')
+        .split(',')
+        .map(|coord| coord.trim().parse::<i32>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("\"{}\" is not a hex, write it as x,y,z e.g. 1,-1,0", text))?;
+    if coords.len() != 3 {
+        return Err(format!(
+            "\"{}\" is not a hex, write it as x,y,z e.g. 1,-1,0",
+            text
+        ));
+    }
+
+    let hex = (coords[0], coords[1], coords[2]);
+    if !is_on_board(hex) {
+        return Err(format!(
+            "hex {} is off the board, coordinates must add up to 0 and be between -2 and 2",
+            format_hex(hex)
+        ));
+    }
+    Ok(hex)
+}
+
+pub fn format_hex(hex: (i32, i32, i32)) -> String {
+    format!("({}, {}, {})", hex.0, hex.1, hex.2)
+}
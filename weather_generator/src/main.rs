@@ -1,11 +1,65 @@
+mod calendar;
+mod campaign;
+mod climate;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use console::style;
 use core::fmt;
-use rand::Rng;
-use std::collections::HashMap;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::path::{self, PathBuf};
+use std::process;
 use std::slice::Iter;
 
-use dialoguer::FuzzySelect;
+use calendar::Transition;
+use campaign::Campaign;
+use climate::Climate;
+use dialoguer::{FuzzySelect, Input};
+
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Generates RPG weather by moving around a seasonal hex flower"
+)]
+struct Cli {
+    /// Climate file (.toml or .json) with the weather on each hex for every season and the edges that can't be
+    /// crossed. Uses the built in climate when not given
+    #[arg(long, value_name = "PATH", global = true)]
+    climate: Option<PathBuf>,
+    /// Seed for the dice rolls, to repeat an earlier session's weather. A random seed is used when not given
+    #[arg(long, global = true)]
+    seed: Option<u64>,
+    /// Campaign file (.json) to carry on from, and to save every generated day to. Created if it does not exist
+    #[arg(long, value_name = "PATH", global = true)]
+    campaign: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the weather for a number of days in one go instead of using the interactive menu
+    Forecast {
+        /// How many days of weather to generate
+        days: u32,
+        /// Season to start in, from the first day of its first month
+        #[arg(long, value_enum)]
+        season: Option<Season>,
+        /// Month to start in, for calendars with named months
+        #[arg(long, conflicts_with = "season")]
+        month: Option<String>,
+        /// Day of the month or season to start on, the first when not given
+        #[arg(long)]
+        day: Option<u32>,
+        /// Hex to start from, as x,y,z coordinates that add up to 0, e.g. 1,-1,0. The centre hex when not given
+        #[arg(long, value_parser = climate::parse_hex, allow_hyphen_values = true)]
+        hex: Option<(i32, i32, i32)>,
+    },
+}
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 enum Season {
     Spring,
     Summer,
@@ -36,7 +90,8 @@ impl fmt::Display for Season {
     }
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 enum Direction {
     TopLeft,
     Top,
@@ -212,275 +267,64 @@ fn hex_wraparound(coords: (i32, i32, i32), dir: Direction) -> (i32, i32, i32) {
     }
 }
 
-fn get_new_coord(coords: (i32, i32, i32), season: Season) -> ((i32, i32, i32), Season) {
-    let mut move_dir: Direction = get_move_direction();
+//Also returns the direction moved in, which is Stay when the roll would cross a blocked edge
+fn get_new_coord<R: Rng>(
+    coords: (i32, i32, i32),
+    season: Season,
+    mut climate: Climate,
+    rng: R,
+) -> ((i32, i32, i32), Season, Direction, Climate, R) {
+    let (mut move_dir, rng): (Direction, R) = get_move_direction(rng);
 
-    if move_dir != Direction::Stay && coords.0.abs() + coords.1.abs() + coords.2.abs() == 4 {
-        match season {
-            Season::Spring => {
-                move_dir = spring_hex_wrapping(coords, move_dir);
-            }
-            Season::Summer => {
-                move_dir = summer_hex_wrapping(coords, move_dir);
-            }
-            Season::Autumn => {
-                move_dir = autumn_hex_wrapping(coords, move_dir);
-            }
-            Season::Winter => {
-                move_dir = winter_hex_wrapping(coords, move_dir);
-            }
-        }
+    if move_dir != Direction::Stay && climate::is_outer_hex(coords) {
+        (move_dir, climate) = season_hex_wrapping(coords, season, move_dir, climate);
 
-        (hex_wraparound(coords, move_dir), season)
+        (
+            hex_wraparound(coords, move_dir),
+            season,
+            move_dir,
+            climate,
+            rng,
+        )
     } else {
         (
             coord_addition(coords, Direction::get_coord_modifier(move_dir)),
             season,
+            move_dir,
+            climate,
+            rng,
         )
     }
 }
 
-fn spring_hex_wrapping(coords: (i32, i32, i32), dir: Direction) -> Direction {
-    match coords {
-        (2, 0, -2) => {
-            if dir == Direction::Top {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (2, -2, 0) => {
-            if dir == Direction::TopRight {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (1, -2, 1) => {
-            if dir == Direction::TopRight {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (-1, -1, 2) => {
-            if dir == Direction::BottomRight {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (-2, 0, 2) => {
-            if dir == Direction::Bottom {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (-2, 1, 1) => {
-            if dir == Direction::BottomLeft || dir == Direction::Bottom {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (-2, 2, -0) => {
-            if dir == Direction::BottomLeft {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (-1, 2, -1) => {
-            if dir == Direction::TopLeft {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (1, 1, -2) => {
-            if dir == Direction::Top {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        _ => dir,
-    }
-}
-
-fn summer_hex_wrapping(coords: (i32, i32, i32), dir: Direction) -> Direction {
-    match coords {
-        (2, 0, -2) => {
-            if dir == Direction::Top {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (2, -1, -1) => {
-            if dir == Direction::Top {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (2, -2, 0) => {
-            if dir == Direction::TopRight {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (-1, -1, 2) => {
-            if dir == Direction::Bottom {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (-2, 0, 2) => {
-            if dir == Direction::Bottom {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (-2, 1, 1) => {
-            if dir == Direction::Bottom {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (-2, 2, -0) => {
-            if dir == Direction::BottomLeft {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (1, 1, -2) => {
-            if dir == Direction::Top {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        _ => dir,
-    }
-}
-
-fn autumn_hex_wrapping(coords: (i32, i32, i32), dir: Direction) -> Direction {
-    match coords {
-        (2, 0, -2) => {
-            if dir == Direction::TopLeft {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (2, -2, 0) => {
-            if dir == Direction::BottomRight {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (1, -2, 1) => {
-            if dir == Direction::BottomRight {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (-2, 1, 1) => {
-            if dir == Direction::Bottom {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (-2, 2, 0) => {
-            if dir == Direction::Bottom {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (0, 2, -2) => {
-            if dir == Direction::Top {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (1, 1, -2) => {
-            if dir == Direction::TopLeft || dir == Direction::Top {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        _ => dir,
-    }
-}
+//Moving across an edge the season's climate blocks keeps the weather where it is
+fn season_hex_wrapping(
+    coords: (i32, i32, i32),
+    season: Season,
+    dir: Direction,
+    climate: Climate,
+) -> (Direction, Climate) {
+    let blocked = match season {
+        Season::Spring => climate.spring.blocked.get(&coords),
+        Season::Summer => climate.summer.blocked.get(&coords),
+        Season::Autumn => climate.autumn.blocked.get(&coords),
+        Season::Winter => climate.winter.blocked.get(&coords),
+    };
 
-fn winter_hex_wrapping(coords: (i32, i32, i32), dir: Direction) -> Direction {
-    match coords {
-        (2, 0, -2) => {
-            if dir == Direction::Top {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (2, -2, 0) => {
-            if dir == Direction::TopRight {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (-2, 0, 2) => {
-            if dir == Direction::Bottom {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (-2, 1, 1) => {
-            if dir == Direction::Bottom {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (-2, 2, 0) => {
-            if dir == Direction::BottomLeft {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        (1, 1, -2) => {
-            if dir == Direction::Top {
-                Direction::Stay
-            } else {
-                dir
-            }
-        }
-        _ => dir,
+    if blocked.is_some_and(|dirs| dirs.contains(&dir)) {
+        (Direction::Stay, climate)
+    } else {
+        (dir, climate)
     }
 }
 
-fn get_move_direction() -> Direction {
-    let roll1 = rand::thread_rng().gen_range(1..=6);
-    let roll2 = rand::thread_rng().gen_range(1..=6);
+fn get_move_direction<R: Rng>(mut rng: R) -> (Direction, R) {
+    let roll1 = rng.gen_range(1..=6);
+    let roll2 = rng.gen_range(1..=6);
 
     let move_roll = roll1 + roll2;
 
-    match move_roll {
+    let dir = match move_roll {
         2 => Direction::TopLeft,
         3 => Direction::BottomLeft,
         4 => Direction::BottomLeft,
@@ -496,160 +340,341 @@ fn get_move_direction() -> Direction {
             println!("Invalid move roll");
             Direction::Stay
         }
-    }
+    };
+
+    (dir, rng)
 }
 
-fn get_weather_string(
-    hex: (i32, i32, i32),
-    season: Season,
-    spring_list: HashMap<(i32, i32, i32), String>,
-    summer_list: HashMap<(i32, i32, i32), String>,
-    autumn_list: HashMap<(i32, i32, i32), String>,
-    winter_list: HashMap<(i32, i32, i32), String>,
-) -> (
-    String,
-    HashMap<(i32, i32, i32), String>,
-    HashMap<(i32, i32, i32), String>,
-    HashMap<(i32, i32, i32), String>,
-    HashMap<(i32, i32, i32), String>,
-) {
+fn get_weather_string(hex: (i32, i32, i32), season: Season, climate: Climate) -> (String, Climate) {
     match season {
         Season::Spring => (
-            spring_list.get(&hex).unwrap().to_string(),
-            spring_list,
-            summer_list,
-            autumn_list,
-            winter_list,
+            climate.spring.weather.get(&hex).unwrap().to_string(),
+            climate,
         ),
         Season::Summer => (
-            summer_list.get(&hex).unwrap().to_string(),
-            spring_list,
-            summer_list,
-            autumn_list,
-            winter_list,
+            climate.summer.weather.get(&hex).unwrap().to_string(),
+            climate,
         ),
         Season::Autumn => (
-            autumn_list.get(&hex).unwrap().to_string(),
-            spring_list,
-            summer_list,
-            autumn_list,
-            winter_list,
+            climate.autumn.weather.get(&hex).unwrap().to_string(),
+            climate,
         ),
         Season::Winter => (
-            winter_list.get(&hex).unwrap().to_string(),
-            spring_list,
-            summer_list,
-            autumn_list,
-            winter_list,
+            climate.winter.weather.get(&hex).unwrap().to_string(),
+            climate,
         ),
     }
 }
 
+//A day of generated weather, numbered from 0 for the day the weather started on. The date is counted in days from
+//the start of the calendar's year
+#[derive(Clone, Serialize, Deserialize)]
+struct Day {
+    number: u32,
+    date: u32,
+    season: Season,
+    hex: (i32, i32, i32),
+    //The direction rolled, or None on the first day of a season, when the calendar's transition rule places the hex
+    movement: Option<Direction>,
+    weather: String,
+}
+
+fn first_day(date: u32, hex: (i32, i32, i32), mut climate: Climate) -> (Day, Climate) {
+    let season: Season = climate.calendar.season_on(date);
+    let weather: String;
+    (weather, climate) = get_weather_string(hex, season, climate);
+
+    (
+        Day {
+            number: 0,
+            date,
+            season,
+            hex,
+            movement: None,
+            weather,
+        },
+        climate,
+    )
+}
+
+//A new season starts wherever the calendar's transition rule says instead of rolling a move
+fn next_day<R: Rng>(today: Day, mut climate: Climate, mut rng: R) -> (Day, Climate, R) {
+    let date: u32 = today.date + 1;
+    let season: Season = climate.calendar.season_on(date);
+    let hex: (i32, i32, i32);
+    let mut movement: Option<Direction> = None;
+
+    if season != today.season {
+        (hex, climate, rng) = transition_hex(today.hex, climate, rng);
+    } else {
+        let move_dir: Direction;
+        (hex, _, move_dir, climate, rng) = get_new_coord(today.hex, season, climate, rng);
+        movement = Some(move_dir);
+    }
+
+    let weather: String;
+    (weather, climate) = get_weather_string(hex, season, climate);
+    (
+        Day {
+            number: today.number + 1,
+            date,
+            season,
+            hex,
+            movement,
+            weather,
+        },
+        climate,
+        rng,
+    )
+}
+
+//Where the weather is on the first day of a new season, following the calendar's transition rule
+fn transition_hex<R: Rng>(
+    hex: (i32, i32, i32),
+    climate: Climate,
+    mut rng: R,
+) -> ((i32, i32, i32), Climate, R) {
+    let new_hex: (i32, i32, i32) = match climate.calendar.transition {
+        Transition::Keep => hex,
+        Transition::Centre => (0, 0, 0),
+        Transition::Random => {
+            let board: Vec<(i32, i32, i32)> = climate::board();
+            board[rng.gen_range(0..board.len())]
+        }
+    };
+
+    (new_hex, climate, rng)
+}
+
+fn print_day(day: Day, climate: Climate) -> (Day, Climate) {
+    if day.movement.is_none() && day.number > 0 {
+        println!("\nIt is now {}", day.season);
+    }
+    println!(
+        "\nDate: {}, Season: {}, Weather: {}\n",
+        climate.calendar.format_date(day.date),
+        day.season,
+        day.weather
+    );
+
+    (day, climate)
+}
+
+fn print_table_header() {
+    println!(
+        "{}",
+        style(format!(
+            "{:>4}  {:<18} {:<7} {:<13} {:<13} {}",
+            "Day", "Date", "Season", "Hex", "Move", "Weather"
+        ))
+        .bold()
+    );
+}
+
+fn print_table_row(day: Day, climate: Climate) -> (Day, Climate) {
+    let movement: String = match day.movement {
+        Some(move_dir) => move_dir.to_string(),
+        None if day.number == 0 => String::from("Start"),
+        None => String::from("New Season"),
+    };
+    println!(
+        "{:>4}  {:<18} {:<7} {:<13} {:<13} {}",
+        day.number,
+        climate.calendar.format_date(day.date),
+        day.season.to_string(),
+        climate::format_hex(day.hex),
+        movement,
+        day.weather
+    );
+
+    (day, climate)
+}
+
+//Moves on one day at a time, printing a row for each day, and returns the new days
+fn generate_forecast<R: Rng>(
+    days: u32,
+    mut today: Day,
+    mut climate: Climate,
+    mut rng: R,
+) -> (Vec<Day>, Climate, R) {
+    println!(
+        "\nStarting from {} on {} in {}, Weather: {}\n",
+        climate::format_hex(today.hex),
+        climate.calendar.format_date(today.date),
+        today.season,
+        today.weather
+    );
+    print_table_header();
+
+    let mut forecast: Vec<Day> = Vec::new();
+    for _ in 0..days {
+        (today, climate, rng) = next_day(today, climate, rng);
+        (today, climate) = print_table_row(today, climate);
+        forecast.push(today.clone());
+    }
+    println!();
+
+    (forecast, climate, rng)
+}
+
+//Only saved when a campaign file was given
+fn save(path: Option<PathBuf>, campaign: Campaign) -> (Option<PathBuf>, Campaign) {
+    if let Some(path) = &path {
+        if let Err(message) = campaign::save_campaign(path, &campaign) {
+            eprintln!("{}", style(message).red());
+        }
+    }
+
+    (path, campaign)
+}
+
+//Forecasts start on a day of the given month, or of the first month in the given season
+fn forecast_start(
+    season: Option<Season>,
+    month: Option<String>,
+    day: u32,
+    climate: Climate,
+) -> (Result<u32, String>, Climate) {
+    let month_start: Result<u32, String> = match (season, month) {
+        (_, Some(month)) => climate
+            .calendar
+            .start_of_month(&month)
+            .ok_or(format!("The calendar has no month called {}", month)),
+        (Some(season), None) => climate
+            .calendar
+            .start_of_season(season)
+            .ok_or(format!("The calendar has no months in {}", season)),
+        (None, None) => Ok(0),
+    };
+
+    match month_start {
+        Ok(month_start) => (climate.calendar.day_of_month(month_start, day), climate),
+        Err(message) => (Err(message), climate),
+    }
+}
+
 fn main() {
-    let mut spring: HashMap<(i32, i32, i32), String> = HashMap::from([
-        ((0, 0, 0), String::from("Clear & Nippy")),
-        ((1, 0, -1), String::from("Sleet")),
-        ((1, -1, 0), String::from("Hail")),
-        ((0, -1, 1), String::from("Cold Wafts of Mist")),
-        ((-1, 0, 1), String::from("Sunny & Clear")),
-        ((-1, 1, 0), String::from("Cloudy & Warm")),
-        ((0, 1, -1), String::from("Nippy & Humid")),
-        ((2, 0, -2), String::from("Heavy Rainfall")),
-        ((2, -1, -1), String::from("Snowy Rain")),
-        ((2, -2, 0), String::from("Windy & Snowy")),
-        ((1, -2, 1), String::from("Heavy Snowfall")),
-        ((0, -2, 2), String::from("Light Snowfall")),
-        ((-1, -1, 2), String::from("Cloudy & Dry")),
-        ((-2, 0, 2), String::from("Pleasantly Warm")),
-        ((-2, 1, 1), String::from("Strong Pollen Drift")),
-        ((-2, 2, 0), String::from("Hot & Dry")),
-        ((-1, 2, -1), String::from("Warm & Humid")),
-        ((0, 2, -2), String::from("Warm Drizzle")),
-        ((1, 1, -2), String::from("Short Showers")),
-    ]);
-
-    let mut summer: HashMap<(i32, i32, i32), String> = HashMap::from([
-        ((0, 0, 0), String::from("Pleasantly Warm")),
-        ((1, 0, -1), String::from("Cloudy & Humid")),
-        ((1, -1, 0), String::from("Cloudy & Windy")),
-        ((0, -1, 1), String::from("Warm Breeze")),
-        ((-1, 0, 1), String::from("Hot & Dry")),
-        ((-1, 1, 0), String::from("Warm & Cloudy")),
-        ((0, 1, -1), String::from("Short, Warm Showers")),
-        ((2, 0, -2), String::from("Torrential Rain")),
-        ((2, -1, -1), String::from("Warm Storm")),
-        ((2, -2, 0), String::from("Fierce Wind")),
-        ((1, -2, 1), String::from("Partly Cloudy & Nippy")),
-        ((0, -2, 2), String::from("Clear & Nippy")),
-        ((-1, -1, 2), String::from("Sunny & Clear")),
-        ((-2, 0, 2), String::from("Dry Heat Surges")),
-        ((-2, 1, 1), String::from("Hot & Windy")),
-        ((-2, 2, 0), String::from("Hot & Muggy")),
-        ((-1, 2, -1), String::from("Warm Drizzle")),
-        ((0, 2, -2), String::from("Warm Rain")),
-        ((1, 1, -2), String::from("Downpour")),
-    ]);
-
-    let mut autumn: HashMap<(i32, i32, i32), String> = HashMap::from([
-        ((0, 0, 0), String::from("Humid & Cloudy")),
-        ((1, 0, -1), String::from("Sunny & Clear")),
-        ((1, -1, 0), String::from("Cold Wafts of Msit")),
-        ((0, -1, 1), String::from("Thick Fog Soup")),
-        ((-1, 0, 1), String::from("Rain & Fog")),
-        ((-1, 1, 0), String::from("Rain & Gusts")),
-        ((0, 1, -1), String::from("Sunny & Cloudy")),
-        ((2, 0, -2), String::from("Indian Summer")),
-        ((2, -1, -1), String::from("Sporadic Gusts")),
-        ((2, -2, 0), String::from("Cold Winds")),
-        ((1, -2, 1), String::from("Frosty & Cloudy")),
-        ((0, -2, 2), String::from("Cloudy & Nippy")),
-        ((-1, -1, 2), String::from("Windy & Clear")),
-        ((-2, 0, 2), String::from("Short, Light Showers")),
-        ((-2, 1, 1), String::from("Heavy Downpour")),
-        ((-2, 2, 0), String::from("Rainy Windstorm")),
-        ((-1, 2, -1), String::from("Drizzle")),
-        ((0, 2, -2), String::from("Sunny & Nippy")),
-        ((1, 1, -2), String::from("Pleasantly Warm")),
-    ]);
-
-    let mut winter: HashMap<(i32, i32, i32), String> = HashMap::from([
-        ((0, 0, 0), String::from("Cold & Humid")),
-        ((1, 0, -1), String::from("Cold Fog Wafts")),
-        ((1, -1, 0), String::from("Cold Rain Showers")),
-        ((0, -1, 1), String::from("Cold & Cloudy")),
-        ((-1, 0, 1), String::from("Wet Snowfall")),
-        ((-1, 1, 0), String::from("Snowy Rain")),
-        ((0, 1, -1), String::from("Clear & Windy")),
-        ((2, 0, -2), String::from("Sunny & Nippy")),
-        ((2, -1, -1), String::from("Light Drizzle")),
-        ((2, -2, 0), String::from("Heavy Rain")),
-        ((1, -2, 1), String::from("Cold Winds")),
-        ((0, -2, 2), String::from("Icy & Cloudy")),
-        ((-1, -1, 2), String::from("Sleet")),
-        ((-2, 0, 2), String::from("Light Snowfall")),
-        ((-2, 1, 1), String::from("Windy & Snowy")),
-        ((-2, 2, 0), String::from("Blizzard")),
-        ((-1, 2, -1), String::from("Hail")),
-        ((0, 2, -2), String::from("Cold & Clear")),
-        ((1, 1, -2), String::from("Cloudy & Nippy")),
-    ]);
-
-    let mut hex: (i32, i32, i32) = (0, 0, 0);
-    let mut season: Season = Season::iterator().as_slice()[FuzzySelect::new()
-        .with_prompt("Choose starting season")
-        .items(Season::iterator().as_slice())
-        .interact()
-        .unwrap()];
-    let mut weather: String;
-    (weather, spring, summer, autumn, winter) =
-        get_weather_string(hex, season, spring, summer, autumn, winter);
+    let cli = Cli::parse();
+    let mut campaign_path: Option<PathBuf> = cli.campaign;
+    let loaded: Option<Campaign> = match &campaign_path {
+        Some(path) => campaign::load_campaign(path).unwrap_or_else(|message| {
+            eprintln!("{}", style(message).red());
+            process::exit(1);
+        }),
+        None => None,
+    };
+
+    //A campaign keeps using the climate it was started with unless told otherwise
+    let climate_path: Option<PathBuf> = cli.climate.or(loaded
+        .as_ref()
+        .and_then(|campaign| campaign.climate.clone()));
+    let mut climate: Climate = match &climate_path {
+        Some(path) => climate::load_climate(path).unwrap_or_else(|message| {
+            eprintln!("{}", style(message).red());
+            process::exit(1);
+        }),
+        None => climate::default_climate(),
+    };
+    //Saved as an absolute path so the campaign can be carried on from any directory
+    let mut campaign: Campaign = Campaign {
+        climate: climate_path.map(|file| path::absolute(&file).unwrap_or(file)),
+        days: loaded.map(|campaign| campaign.days).unwrap_or_default(),
+    };
 
+    //The same seed and choices always give the same weather, so print it to allow a session to be replayed
+    let seed: u64 = cli.seed.unwrap_or_else(rand::random);
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
     println!(
-        "\nStarting Season: {}, Starting Weather: {}\n",
-        season, weather
+        "Seed: {} (run with --seed {} to replay this session)",
+        seed, seed
     );
 
-    let operations: [&str; 3] = ["Generate Weather", "Change Season", "Exit"];
+    let mut today: Day;
+
+    if let Some(Command::Forecast {
+        days,
+        season,
+        month,
+        day,
+        hex,
+    }) = cli.command
+    {
+        //A campaign's forecast carries on from its last day
+        if campaign.days.is_empty() {
+            let date: Result<u32, String>;
+            (date, climate) = forecast_start(season, month, day.unwrap_or(1), climate);
+            let date: u32 = date.unwrap_or_else(|message| {
+                eprintln!("{}", style(message).red());
+                process::exit(1);
+            });
+            (today, climate) = first_day(date, hex.unwrap_or((0, 0, 0)), climate);
+            campaign.days.push(today);
+        } else if season.is_some() || month.is_some() || day.is_some() || hex.is_some() {
+            eprintln!(
+                "{}",
+                style("The forecast carries on from the campaign's last day, so it can't be given a season, month, day or hex").red()
+            );
+            process::exit(1);
+        }
+
+        let forecast: Vec<Day>;
+        (forecast, _, _) =
+            generate_forecast(days, campaign.days.last().unwrap().clone(), climate, rng);
+        campaign.days.extend(forecast);
+        save(campaign_path, campaign);
+        return;
+    }
+
+    if let Some(today) = campaign.days.last() {
+        println!(
+            "\nContinuing the campaign on day {}, Date: {}, Season: {}, Weather: {}\n",
+            today.number,
+            climate.calendar.format_date(today.date),
+            today.season,
+            today.weather
+        );
+    } else {
+        //Calendars with named months start at the beginning of a month, others at the beginning of a season
+        let date: u32 = if climate.calendar.named_months {
+            let names: Vec<&str> = climate
+                .calendar
+                .months
+                .iter()
+                .map(|month| month.name.as_str())
+                .collect();
+            let month: usize = FuzzySelect::new()
+                .with_prompt("Choose starting month")
+                .items(&names)
+                .interact()
+                .unwrap();
+            climate.calendar.start_of_month(names[month]).unwrap()
+        } else {
+            let season: Season = Season::iterator().as_slice()[FuzzySelect::new()
+                .with_prompt("Choose starting season")
+                .items(Season::iterator().as_slice())
+                .interact()
+                .unwrap()];
+            climate.calendar.start_of_season(season).unwrap()
+        };
+        (today, climate) = first_day(date, (0, 0, 0), climate);
+
+        println!(
+            "\nStarting Date: {}, Starting Season: {}, Starting Weather: {}\n",
+            climate.calendar.format_date(today.date),
+            today.season,
+            today.weather
+        );
+        campaign.days.push(today);
+        (campaign_path, campaign) = save(campaign_path, campaign);
+    }
+
+    let operations: [&str; 5] = [
+        "Generate Weather",
+        "Generate Forecast",
+        "Change Season",
+        "Show History",
+        "Exit",
+    ];
 
     loop {
         let operation = FuzzySelect::new()
@@ -659,20 +684,59 @@ fn main() {
             .unwrap();
 
         if operation == 0 {
-            (hex, season) = get_new_coord(hex, season);
-            (weather, spring, summer, autumn, winter) =
-                get_weather_string(hex, season, spring, summer, autumn, winter);
-            println!("\nSeason: {}, Weather: {}\n", season, weather);
+            (today, climate, rng) = next_day(campaign.days.last().unwrap().clone(), climate, rng);
+            (today, climate) = print_day(today, climate);
+            campaign.days.push(today);
+            (campaign_path, campaign) = save(campaign_path, campaign);
         } else if operation == 1 {
-            season = Season::iterator().as_slice()[FuzzySelect::new()
+            let days: u32 = Input::new()
+                .with_prompt("How many days?")
+                .default(30)
+                .interact_text()
+                .unwrap();
+            let forecast: Vec<Day>;
+            (forecast, climate, rng) =
+                generate_forecast(days, campaign.days.last().unwrap().clone(), climate, rng);
+            campaign.days.extend(forecast);
+            (campaign_path, campaign) = save(campaign_path, campaign);
+        } else if operation == 2 {
+            let season: Season = Season::iterator().as_slice()[FuzzySelect::new()
                 .with_prompt("Choose starting season")
                 .items(Season::iterator().as_slice())
                 .interact()
                 .unwrap()];
-            hex = (0, 0, 0);
-            (weather, spring, summer, autumn, winter) =
-                get_weather_string(hex, season, spring, summer, autumn, winter);
-            println!("\nSeason: {}, Weather: {}\n", season, weather);
+            //Jumps to the start of the season on the next day, moving hex the same way as when a season ends.
+            //A campaign that is still on its starting day starts over in the new season instead
+            match climate.calendar.start_of_season(season) {
+                Some(date) => {
+                    let hex: (i32, i32, i32);
+                    (hex, climate, rng) =
+                        transition_hex(campaign.days.last().unwrap().hex, climate, rng);
+                    (today, climate) = first_day(date, hex, climate);
+                    if campaign.days.len() == 1 {
+                        campaign.days.clear();
+                    } else {
+                        today.number = campaign.days.last().unwrap().number + 1;
+                    }
+                    (today, climate) = print_day(today, climate);
+                    campaign.days.push(today);
+                    (campaign_path, campaign) = save(campaign_path, campaign);
+                }
+                None => println!(
+                    "{}",
+                    style(format!("The calendar has no months in {}", season)).red()
+                ),
+            }
+        } else if operation == 3 {
+            println!();
+            print_table_header();
+            let mut shown: Vec<Day> = Vec::new();
+            for mut day in campaign.days {
+                (day, climate) = print_table_row(day, climate);
+                shown.push(day);
+            }
+            campaign.days = shown;
+            println!();
         } else {
             break;
         }